@@ -1,82 +1,124 @@
-use crate::view::widgets::{CrateItemList, FeatureItemList, ItemListStatus};
-use strum::{Display, EnumIter, FromRepr};
+use serde::Serialize;
+
+use crate::{
+    scraper::TeamStatus,
+    view::widgets::{CrateItemList, FeatureItemList, ItemListStatus},
+};
 
 ///This struct represent the data as seen in the actual page
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Crates {
     pub name: String,
     pub description: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub features: Option<Vec<String>>,
+    pub curation: Curation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+///Who recommends a crate, a team catalog can approve or discourage crates on top of blessed.rs
+#[derive(Serialize, Default, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Curation {
+    #[default]
+    Upstream,
+    TeamApproved,
+    TeamDiscouraged,
+}
+
+impl From<Option<TeamStatus>> for Curation {
+    fn from(value: Option<TeamStatus>) -> Self {
+        match value {
+            None => Self::Upstream,
+            Some(TeamStatus::Approved) => Self::TeamApproved,
+            //Hidden crates are removed before getting here
+            Some(TeamStatus::Discouraged | TeamStatus::Hidden) => Self::TeamDiscouraged,
+        }
+    }
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TableEntry {
     pub use_case: String,
     pub crates: Vec<Crates>,
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Table {
     pub entries: Vec<TableEntry>,
 }
 
+impl Table {
+    ///Keeps only the crates which satisfy the predicate, dropping the entries left without crates
+    pub fn filter(&self, predicate: impl Fn(&TableEntry, &Crates) -> bool) -> Table {
+        Table {
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| TableEntry {
+                    use_case: entry.use_case.clone(),
+                    crates: entry
+                        .crates
+                        .iter()
+                        .filter(|krate| predicate(entry, krate))
+                        .cloned()
+                        .collect(),
+                })
+                .filter(|entry| !entry.crates.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.iter().all(|entry| entry.crates.is_empty())
+    }
+}
+
+///A category as listed in blessed.rs e.g General, Common, Math / Scientific
+#[derive(Serialize, Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Category {
+    pub name: String,
+    pub table: Table,
+}
+
+impl Category {
+    pub fn new(name: String, table: Table) -> Self {
+        Self { name, table }
+    }
+}
+
 impl From<crate::backend::Table> for Vec<CrateItemList> {
     fn from(val: crate::backend::Table) -> Self {
         let mut items: Vec<CrateItemList> = vec![];
 
         for entr in val.entries {
             for krate in entr.crates {
-                items.push(CrateItemList::new(
-                    krate.name.clone(),
-                    krate.description.clone(),
-                    ItemListStatus::default(),
-                    krate.features.as_ref().map(|features| {
-                        features
-                            .iter()
-                            .map(|feat| FeatureItemList::new(feat.clone()))
-                            .collect()
-                    }),
-                ));
+                //A crate recommended for more than one use case is listed under the first one
+                if items.iter().any(|item| item.name == krate.name) {
+                    continue;
+                }
+
+                items.push(CrateItemList {
+                    registry: krate.registry.clone(),
+                    ..CrateItemList::new(
+                        krate.name.clone(),
+                        krate.description.clone(),
+                        entr.use_case.clone(),
+                        krate.curation,
+                        ItemListStatus::default(),
+                        krate.features.as_ref().map(|features| {
+                            features
+                                .iter()
+                                .map(|feat| FeatureItemList::new(feat.clone()))
+                                .collect()
+                        }),
+                    )
+                });
             }
         }
-        items.sort();
-
-        items.dedup();
 
         items
     }
 }
-
-#[derive(Debug, Clone, FromRepr, Display, EnumIter)]
-pub enum Categories {
-    #[strum(to_string = "math-scientific")]
-    Math,
-
-    #[strum(to_string = "ffi")]
-    FFI,
-
-    #[strum(to_string = "cryptography")]
-    Cryptography,
-}
-
-#[derive(Debug, Clone, FromRepr, Display, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
-pub enum CategoriesWithSubCategories {
-    #[strum(to_string = "common")]
-    Common,
-
-    #[strum(to_string = "concurrency")]
-    Concurrency,
-
-    #[strum(to_string = "networking")]
-    Networking,
-
-    #[strum(to_string = "databases")]
-    Databases,
-
-    #[strum(to_string = "cli-tools")]
-    Clis,
-
-    #[strum(to_string = "graphics")]
-    Graphics,
-}
@@ -0,0 +1,163 @@
+/// This module's job is to keep the last crates list downloaded from blessed.rs on disk, so the
+/// app can start instantly and keep working offline
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::scraper::{CratesData, BLESSED_RS_URL};
+
+/// The crates list as it was saved the last time it was downloaded successfully
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedCatalog {
+    pub fetched_at: DateTime<Utc>,
+    pub data: CratesData,
+}
+
+/// Where the crates list shown in the app comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CatalogStatus {
+    ///The cached crates list is being shown while a new one is downloaded
+    Refreshing(DateTime<Utc>),
+    UpToDate,
+    ///The download failed, so the crates list shown is the one cached at this date
+    Offline(DateTime<Utc>),
+}
+
+impl CatalogStatus {
+    pub fn fetched_at_label(date: DateTime<Utc>) -> String {
+        date.with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+}
+
+///Turns anything, like a url, into something that can be used in a file name. It is the 64 bit
+///FNV-1a hash, unlike the hasher of std it never changes between Rust releases so the cached
+///files are found again after upgrading
+pub fn hash_key(key: &str) -> String {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let hash = key.bytes().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    });
+
+    format!("{hash:016x}")
+}
+
+///Writes to a temporary file first so a crash never leaves a half written file behind
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+
+    fs::write(&temporary_path, contents)?;
+    fs::rename(temporary_path, path)
+}
+
+///Every url gets its own cache file so switching between sources never mixes their crates
+fn cache_file(url: &str) -> Option<PathBuf> {
+    let file_name = if url == BLESSED_RS_URL {
+        "crates.json".to_string()
+    } else {
+        format!("crates-{}.json", hash_key(url))
+    };
+
+    dirs::cache_dir().map(|dir| dir.join("get-blessed").join(file_name))
+}
+
+pub fn load_catalog(url: &str) -> Option<CachedCatalog> {
+    load_catalog_from(&cache_file(url)?)
+}
+
+pub fn save_catalog(url: &str, data: &CratesData) -> io::Result<()> {
+    let path = cache_file(url).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "could not find the cache directory",
+        )
+    })?;
+
+    save_catalog_to(&path, data)
+}
+
+fn load_catalog_from(path: &Path) -> Option<CachedCatalog> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_catalog_to(path: &Path, data: &CratesData) -> io::Result<()> {
+    let cached = CachedCatalog {
+        fetched_at: Utc::now(),
+        data: data.clone(),
+    };
+
+    write_atomically(path, serde_json::to_string(&cached)?.as_bytes())
+}
+
+///A directory of its own for a test, in the system's temporary directory. It is created empty
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("get-blessed-{name}-{}", std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scraper::{Group, Purpose, Recommendation};
+
+    use super::*;
+
+    #[test]
+    fn saved_catalog_can_be_loaded_back() {
+        let path = test_dir("catalog").join("crates.json");
+
+        let data = CratesData {
+            crate_groups: vec![Group {
+                name: "Common".to_string(),
+                subgroups: None,
+                purposes: Some(vec![Purpose {
+                    name: "Error handling".to_string(),
+                    recommendations: vec![Recommendation {
+                        name: "anyhow".to_string(),
+                        notes: None,
+                        ..Recommendation::default()
+                    }],
+                }]),
+            }],
+        };
+
+        save_catalog_to(&path, &data).unwrap();
+
+        let cached = load_catalog_from(&path).unwrap();
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert_eq!(cached.data, data);
+    }
+
+    #[test]
+    fn cache_keys_do_not_change_between_releases() {
+        assert_eq!(hash_key(""), "cbf29ce484222325");
+        assert_eq!(hash_key("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn missing_cache_is_not_an_error() {
+        let dir = test_dir("missing-cache");
+        let loaded = load_catalog_from(&dir.join("crates.json"));
+
+        fs::remove_dir_all(dir).ok();
+
+        assert!(loaded.is_none());
+    }
+}
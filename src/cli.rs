@@ -0,0 +1,126 @@
+/// This module's job is to define the arguments the program accepts
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{export::ExportFormat, scraper::CatalogSource};
+
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Where to read the crates list from: a URL, the path to a crates.json file or `-` to read it
+    /// from stdin
+    #[arg(
+        long,
+        short,
+        env = "GET_BLESSED_SOURCE",
+        value_name = "URL|PATH|-",
+        global = true
+    )]
+    pub source: Option<CatalogSource>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Without a command the interactive terminal ui is started
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Add crates to the current project without opening the terminal ui
+    Add(AddArgs),
+    /// List the recommended crates
+    List(ListArgs),
+    /// Search the recommended crates by name, description or use case
+    Search(SearchArgs),
+    /// List the presets and the saved profiles which can be added with `add --preset` and
+    /// `add --profile`
+    Presets,
+}
+
+#[derive(Args, Debug)]
+pub struct AddArgs {
+    /// Crates to add, a version requirement can be given like `serde@=1.0.100`
+    #[arg(
+        required_unless_present_any = ["preset", "profile"],
+        value_name = "CRATE[@VERSION]"
+    )]
+    pub crates: Vec<String>,
+
+    /// Features to enable, as `crate:feature`, separated by commas or repeating the flag
+    #[arg(long, short = 'F', value_delimiter = ',', value_name = "CRATE:FEATURE")]
+    pub features: Vec<String>,
+
+    /// Crates to add without their default features, separated by commas or repeating the flag
+    #[arg(long, value_delimiter = ',', value_name = "CRATE")]
+    pub no_default_features: Vec<String>,
+
+    /// Crates to add to `[dev-dependencies]`, separated by commas or repeating the flag
+    #[arg(long, value_delimiter = ',', value_name = "CRATE")]
+    pub dev: Vec<String>,
+
+    /// Crates to add to `[build-dependencies]`, separated by commas or repeating the flag
+    #[arg(long, value_delimiter = ',', value_name = "CRATE")]
+    pub build: Vec<String>,
+
+    /// Crates to add from a registry of `.cargo/config.toml`, as `crate:registry`
+    #[arg(long, value_delimiter = ',', value_name = "CRATE:REGISTRY")]
+    pub registry: Vec<String>,
+
+    /// Workspace packages to add the crates to, separated by commas or repeating the flag
+    #[arg(long, value_delimiter = ',', value_name = "PACKAGE")]
+    pub package: Vec<String>,
+
+    /// Write the versions to `[workspace.dependencies]` and `crate.workspace = true` to the packages
+    #[arg(long)]
+    pub workspace_dependencies: bool,
+
+    /// Add the crates of a preset e.g. `web-backend` or `cli`
+    #[arg(long, short)]
+    pub preset: Option<String>,
+
+    /// Add the crates of a profile saved from the terminal ui, by its name or the path to its file
+    #[arg(long, value_name = "NAME|PATH")]
+    pub profile: Option<String>,
+
+    /// Print the changes to Cargo.toml without writing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Restore Cargo.toml and Cargo.lock when any of the crates could not be added
+    #[arg(long, conflicts_with = "dry_run")]
+    pub rollback: bool,
+
+    /// Print the crates in this format instead of adding them to Cargo.toml
+    #[arg(long, value_name = "FORMAT", conflicts_with_all = ["dry_run", "rollback"])]
+    pub export: Option<ExportFormat>,
+
+    /// Write the export to this file instead of printing it
+    #[arg(long, short, requires = "export")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    /// Only list the crates of this category e.g. `networking`
+    #[arg(long, short)]
+    pub category: Option<String>,
+
+    /// Print the crates as json instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SearchArgs {
+    /// Words the crates must contain
+    #[arg(required = true)]
+    pub query: Vec<String>,
+
+    /// Only search the crates of this category e.g. `networking`
+    #[arg(long, short)]
+    pub category: Option<String>,
+
+    /// Print the crates as json instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
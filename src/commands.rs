@@ -0,0 +1,686 @@
+/// This module's job is to run the commands which do not need the terminal ui
+use std::{collections::BTreeMap, process::ExitCode};
+
+use crate::{
+    backend::Category,
+    cache::{load_catalog, save_catalog, CatalogStatus},
+    cli::{AddArgs, ListArgs, SearchArgs},
+    content_parser::{jsoncontentparser::JsonContentParser, ContentParser},
+    dependency_builder::{
+        CrateToAdd, DependenciesBuilder, DependencyKind, DependencyPreview, ManifestBackup,
+        WorkspaceTarget,
+    },
+    export::{find_latest_versions, render, write_export, ExportFormat},
+    index::{IndexClient, CRATES_IO_INDEX_URL},
+    preset::{all_presets, find_preset, Preset},
+    profile::{load_profile, load_profiles},
+    registry::Registries,
+    scraper::{load_crates_data, CatalogSource, CratesData},
+    team_catalog::merge_team_catalogs,
+    workspace::Workspace,
+};
+
+/// Same exit code clap uses when the arguments are wrong
+const USAGE_ERROR: u8 = 2;
+
+pub fn add(args: &AddArgs, user_presets: &[Preset]) -> ExitCode {
+    let crates_to_add = match crates_to_add(args, user_presets) {
+        Ok(crates_to_add) => crates_to_add,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    let target = match workspace_target(args) {
+        Ok(target) => target,
+        Err((code, e)) => {
+            eprintln!("error: {e}");
+            return code;
+        }
+    };
+
+    if args.dry_run {
+        return print_preview(
+            &DependenciesBuilder::new(crates_to_add)
+                .target(target)
+                .preview(),
+        );
+    }
+
+    let crate_names: Vec<&str> = crates_to_add
+        .iter()
+        .map(|crate_to_add| crate_to_add.crate_name.as_str())
+        .collect();
+
+    println!("Adding {}", crate_names.join(", "));
+
+    let report = DependenciesBuilder::new(crates_to_add)
+        .target(target)
+        .add_dependencies();
+
+    for result in &report.results {
+        let crate_name = match &result.package {
+            Some(package) => format!("{} → {package}", result.crate_name),
+            None => result.crate_name.clone(),
+        };
+
+        match &result.error {
+            Some(e) => eprintln!("✗ {crate_name}: {e}"),
+            None => println!("✓ {crate_name}"),
+        }
+    }
+
+    if !report.has_failures() {
+        println!("Dependencies added successfully ✓");
+        return ExitCode::SUCCESS;
+    }
+
+    if args.rollback {
+        match report.backup.as_ref().map(ManifestBackup::restore) {
+            Some(Ok(())) => eprintln!("Cargo.toml was restored, no crate was added"),
+            Some(Err(e)) => eprintln!("error: could not restore Cargo.toml: {e}"),
+            None => eprintln!("error: Cargo.toml could not be backed up, so it was not restored"),
+        }
+    }
+
+    ExitCode::FAILURE
+}
+
+///Prints the crates, or writes them to `--output`, in the format of `--export` instead of adding
+///them. The crates without a version requirement get the latest one from their index
+pub async fn export(args: &AddArgs, format: ExportFormat, user_presets: &[Preset]) -> ExitCode {
+    let crates_to_add = match crates_to_add(args, user_presets) {
+        Ok(crates_to_add) => crates_to_add,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::from(USAGE_ERROR);
+        }
+    };
+
+    let mut latest_versions = BTreeMap::new();
+
+    if format != ExportFormat::Script {
+        let crates_io = IndexClient::new(CRATES_IO_INDEX_URL);
+
+        for e in find_latest_versions(
+            &crates_to_add,
+            &mut latest_versions,
+            &crates_io,
+            &Registries::detect(),
+        )
+        .await
+        {
+            eprintln!("warning: {e}");
+        }
+    }
+
+    let contents = match render(&crates_to_add, format, &latest_versions) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let Some(path) = &args.output else {
+        print!("{contents}");
+        return ExitCode::SUCCESS;
+    };
+
+    match write_export(path, &contents, format) {
+        Ok(()) => {
+            eprintln!("Exported to {}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: could not write {}: {e}", path.display());
+            ExitCode::FAILURE
+        }
+    }
+}
+
+///`None` keeps the default of `cargo add`, which is adding to the package of the current directory.
+///Fails with `1` when the workspace is not what the arguments expect and with `2` when they are
+///not enough
+fn workspace_target(args: &AddArgs) -> Result<Option<WorkspaceTarget>, (ExitCode, String)> {
+    if args.package.is_empty() && !args.workspace_dependencies {
+        return Ok(None);
+    }
+
+    let workspace = Workspace::detect().map_err(|e| (ExitCode::FAILURE, e.to_string()))?;
+
+    if let Some(package) = args
+        .package
+        .iter()
+        .find(|package| workspace.member(package).is_none())
+    {
+        return Err((
+            ExitCode::FAILURE,
+            format!("{package} is not a package of this workspace"),
+        ));
+    }
+
+    let packages = if args.package.is_empty() {
+        let current_package = workspace.current_package.clone().ok_or((
+            ExitCode::from(USAGE_ERROR),
+            "at the root of a virtual workspace the packages have to be chosen with --package"
+                .to_string(),
+        ))?;
+        vec![current_package]
+    } else {
+        args.package.clone()
+    };
+
+    Ok(Some(WorkspaceTarget {
+        workspace,
+        packages,
+        use_workspace_dependencies: args.workspace_dependencies,
+    }))
+}
+
+///Exits with `1` when `cargo add` would fail for any of the crates
+fn print_preview(previews: &[DependencyPreview]) -> ExitCode {
+    for (index, preview) in previews.iter().enumerate() {
+        // The previews come grouped by table
+        if index == 0 || previews[index - 1].table_header() != preview.table_header() {
+            if index > 0 {
+                println!();
+            }
+            println!("{}", preview.table_header());
+        }
+
+        if let Some(e) = &preview.error {
+            println!("✗ {}: {e}", preview.crate_name);
+            continue;
+        }
+
+        if let Some(current_entry) = &preview.current_entry {
+            println!("- {current_entry}");
+        }
+        if preview.is_removal {
+            continue;
+        }
+
+        println!("+ {}", preview.new_entry());
+
+        if let Some(workspace_entry) = &preview.workspace_entry {
+            println!("+ {workspace_entry} in [workspace.dependencies]");
+        }
+
+        if !preview.enabled_features.is_empty() {
+            println!(
+                "    enabled features: {}",
+                preview.enabled_features.join(", ")
+            );
+        }
+    }
+
+    if previews.iter().any(|preview| preview.error.is_some()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+pub async fn list(
+    args: &ListArgs,
+    source: &CatalogSource,
+    team_catalogs: &[CratesData],
+) -> ExitCode {
+    let categories = match load_categories(source, team_catalogs, args.category.as_deref()).await {
+        Ok(categories) => categories,
+        Err((exit_code, e)) => {
+            eprintln!("error: {e}");
+            return exit_code;
+        }
+    };
+
+    print_categories(&categories, args.json);
+
+    ExitCode::SUCCESS
+}
+
+pub fn presets(user_presets: &[Preset]) -> ExitCode {
+    for preset in all_presets(user_presets) {
+        println!("{}  {}", preset.name, preset.description);
+        println!("    {}", preset.summary());
+    }
+
+    let profiles = load_profiles();
+
+    if !profiles.is_empty() {
+        println!("\nSaved profiles, added with `add --profile`:");
+    }
+
+    for profile in profiles {
+        println!("{}  {}", profile.name, profile.description);
+        println!("    {}", profile.summary());
+    }
+
+    ExitCode::SUCCESS
+}
+
+/// Like grep, exits with `1` when no crate matches
+pub async fn search(
+    args: &SearchArgs,
+    source: &CatalogSource,
+    team_catalogs: &[CratesData],
+) -> ExitCode {
+    let categories = match load_categories(source, team_catalogs, args.category.as_deref()).await {
+        Ok(categories) => categories,
+        Err((exit_code, e)) => {
+            eprintln!("error: {e}");
+            return exit_code;
+        }
+    };
+
+    let words: Vec<String> = args.query.iter().map(|word| word.to_lowercase()).collect();
+
+    let found: Vec<Category> = categories
+        .into_iter()
+        .map(|category| {
+            let table = category.table.filter(|entry, krate| {
+                let searchable = format!("{} {} {}", krate.name, krate.description, entry.use_case)
+                    .to_lowercase();
+                words.iter().all(|word| searchable.contains(word))
+            });
+            Category::new(category.name, table)
+        })
+        .filter(|category| !category.table.is_empty())
+        .collect();
+
+    if found.is_empty() && !args.json {
+        eprintln!("No crates found for `{}`", args.query.join(" "));
+    } else {
+        print_categories(&found, args.json);
+    }
+
+    if found.is_empty() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+///The crates list is always downloaded again, the cached one is only used when that fails
+async fn load_crates(source: &CatalogSource) -> Result<CratesData, String> {
+    match load_crates_data(source).await {
+        Ok(crates_data) => {
+            if let CatalogSource::Url(url) = source {
+                save_catalog(url, &crates_data).ok();
+            }
+            Ok(crates_data)
+        }
+        Err(e) => {
+            let cached = match source {
+                CatalogSource::Url(url) => load_catalog(url),
+                CatalogSource::File(_) | CatalogSource::Stdin => None,
+            };
+
+            match cached {
+                Some(cached) => {
+                    eprintln!(
+                        "warning: could not download the crates list, using the one from {}",
+                        CatalogStatus::fetched_at_label(cached.fetched_at)
+                    );
+                    Ok(cached.data)
+                }
+                None => Err(format!("Could not load the crates list from {source}: {e}")),
+            }
+        }
+    }
+}
+
+async fn load_categories(
+    source: &CatalogSource,
+    team_catalogs: &[CratesData],
+    category_name: Option<&str>,
+) -> Result<Vec<Category>, (ExitCode, String)> {
+    let crates_data = load_crates(source)
+        .await
+        .map_err(|e| (ExitCode::FAILURE, e))?;
+
+    let categories =
+        JsonContentParser::parse_content(&merge_team_catalogs(crates_data, team_catalogs))
+            .get_categories();
+
+    let Some(category_name) = category_name else {
+        return Ok(categories);
+    };
+
+    //Categories are matched ignoring case and punctuation so `math` finds `Math / Scientific`
+    let normalize = |name: &str| -> String {
+        name.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    let wanted = normalize(category_name);
+
+    let (found, others): (Vec<Category>, Vec<Category>) = categories
+        .into_iter()
+        .partition(|category| normalize(&category.name).starts_with(&wanted));
+
+    if found.is_empty() {
+        let names: Vec<String> = others.into_iter().map(|category| category.name).collect();
+        return Err((
+            ExitCode::from(USAGE_ERROR),
+            format!(
+                "There is no category called `{category_name}`, the categories are: {}",
+                names.join(", ")
+            ),
+        ));
+    }
+
+    Ok(found)
+}
+
+fn print_categories(categories: &[Category], as_json: bool) {
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(categories).unwrap_or_default()
+        );
+        return;
+    }
+
+    let mut rows: Vec<[String; 4]> = vec![[
+        "NAME".to_string(),
+        "CATEGORY".to_string(),
+        "USE CASE".to_string(),
+        "DESCRIPTION".to_string(),
+    ]];
+
+    for category in categories {
+        for entry in &category.table.entries {
+            for krate in &entry.crates {
+                rows.push([
+                    krate.name.clone(),
+                    category.name.clone(),
+                    entry.use_case.clone(),
+                    krate
+                        .description
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                ]);
+            }
+        }
+    }
+
+    let width = |column: usize| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or_default()
+    };
+
+    let (name_width, category_width, use_case_width) = (width(0), width(1), width(2));
+
+    for [name, category, use_case, description] in &rows {
+        println!(
+            "{name:name_width$}  {category:category_width$}  {use_case:use_case_width$}  {description}"
+        );
+    }
+}
+
+///Joins the crates of the preset with the ones passed as arguments and sets their features
+fn crates_to_add(args: &AddArgs, user_presets: &[Preset]) -> Result<Vec<CrateToAdd>, String> {
+    let mut crates_to_add: Vec<CrateToAdd> = match &args.preset {
+        Some(preset_name) => {
+            find_preset(preset_name, user_presets)
+                .ok_or_else(|| {
+                    let presets: Vec<String> = all_presets(user_presets)
+                        .into_iter()
+                        .map(|preset| preset.name)
+                        .collect();
+                    format!(
+                        "There is no preset called `{preset_name}`, the available presets are: {}",
+                        presets.join(", ")
+                    )
+                })?
+                .crates
+        }
+        None => Vec::new(),
+    };
+
+    if let Some(profile) = &args.profile {
+        for krate in load_profile(profile).map_err(|e| e.to_string())?.crates {
+            crates_to_add.retain(|crate_to_add| crate_to_add.crate_name != krate.crate_name);
+            crates_to_add.push(krate);
+        }
+    }
+
+    for krate in &args.crates {
+        // Like cargo add, `serde@=1.0.100` asks for a version
+        let (crate_name, version) = match krate.split_once('@') {
+            Some((crate_name, version)) => (crate_name, Some(version.to_string())),
+            None => (krate.as_str(), None),
+        };
+
+        match crates_to_add
+            .iter_mut()
+            .find(|crate_to_add| crate_to_add.crate_name == crate_name)
+        {
+            Some(existing) => existing.version = version.or(existing.version.take()),
+            None => crates_to_add.push(CrateToAdd {
+                version,
+                ..CrateToAdd::new(crate_name)
+            }),
+        }
+    }
+
+    for feature in &args.features {
+        let (crate_name, feature) = feature
+            .split_once(':')
+            .or_else(|| feature.split_once('/'))
+            .ok_or_else(|| format!("`{feature}` must be written as `crate:feature`"))?;
+
+        let krate = crates_to_add
+            .iter_mut()
+            .find(|crate_to_add| crate_to_add.crate_name == crate_name)
+            .ok_or_else(|| {
+                format!("The feature `{feature}` is for `{crate_name}` which is not being added")
+            })?;
+
+        let features = krate.features.get_or_insert_with(Vec::new);
+        if !features.iter().any(|name| name == feature) {
+            features.push(feature.to_string());
+        }
+    }
+
+    for registry in &args.registry {
+        let (crate_name, registry) = registry
+            .split_once(':')
+            .ok_or_else(|| format!("`{registry}` must be written as `crate:registry`"))?;
+
+        crates_to_add
+            .iter_mut()
+            .find(|crate_to_add| crate_to_add.crate_name == crate_name)
+            .ok_or_else(|| format!("`{crate_name}` is not being added"))?
+            .registry = Some(registry.to_string());
+    }
+
+    for (crate_names, kind) in [
+        (&args.dev, DependencyKind::Dev),
+        (&args.build, DependencyKind::Build),
+    ] {
+        for crate_name in crate_names {
+            crates_to_add
+                .iter_mut()
+                .find(|crate_to_add| &crate_to_add.crate_name == crate_name)
+                .ok_or_else(|| format!("`{crate_name}` is not being added"))?
+                .kind = kind;
+        }
+    }
+
+    for crate_name in &args.no_default_features {
+        crates_to_add
+            .iter_mut()
+            .find(|crate_to_add| &crate_to_add.crate_name == crate_name)
+            .ok_or_else(|| format!("`{crate_name}` is not being added"))?
+            .default_features = false;
+    }
+
+    Ok(crates_to_add)
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use crate::{
+        cache::test_dir,
+        cli::{Cli, Command},
+    };
+
+    use super::*;
+
+    fn parse_add(args: &[&str]) -> Result<Vec<CrateToAdd>, String> {
+        let cli = Cli::parse_from(["get-blessed", "add"].iter().chain(args));
+        let Some(Command::Add(args)) = cli.command else {
+            panic!("expected the add command");
+        };
+        crates_to_add(&args, &[])
+    }
+
+    #[test]
+    fn features_are_set_on_their_crate() {
+        let crates_to_add =
+            parse_add(&["serde", "tokio", "--features", "tokio:full,tokio:macros"]).unwrap();
+
+        assert_eq!(
+            crates_to_add,
+            vec![
+                CrateToAdd::new("serde"),
+                CrateToAdd {
+                    features: Some(vec!["full".to_string(), "macros".to_string()]),
+                    ..CrateToAdd::new("tokio")
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn registries_are_set_on_their_crate() {
+        let crates_to_add = parse_add(&[
+            "serde",
+            "company-logging",
+            "--registry",
+            "company-logging:internal",
+        ])
+        .unwrap();
+
+        assert_eq!(crates_to_add[0].registry, None);
+        assert_eq!(crates_to_add[1].registry.as_deref(), Some("internal"));
+        assert!(parse_add(&["serde", "--registry", "tokio:internal"]).is_err());
+    }
+
+    #[test]
+    fn versions_are_read_like_cargo_add() {
+        let crates_to_add = parse_add(&["serde@=1.0.100", "tokio"]).unwrap();
+
+        assert_eq!(crates_to_add[0].crate_name, "serde");
+        assert_eq!(crates_to_add[0].version.as_deref(), Some("=1.0.100"));
+        assert_eq!(crates_to_add[1].version, None);
+    }
+
+    #[test]
+    fn default_features_can_be_turned_off_per_crate() {
+        let crates_to_add =
+            parse_add(&["serde", "tokio", "--no-default-features", "tokio"]).unwrap();
+
+        assert!(crates_to_add[0].default_features);
+        assert!(!crates_to_add[1].default_features);
+    }
+
+    #[test]
+    fn crates_can_be_added_as_dev_or_build_dependencies() {
+        let crates_to_add =
+            parse_add(&["serde", "insta", "cc", "--dev", "insta", "--build", "cc"]).unwrap();
+
+        let kinds: Vec<DependencyKind> = crates_to_add
+            .iter()
+            .map(|crate_to_add| crate_to_add.kind)
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                DependencyKind::Normal,
+                DependencyKind::Dev,
+                DependencyKind::Build
+            ]
+        );
+    }
+
+    #[test]
+    fn preset_crates_are_joined_with_the_arguments() {
+        let crates_to_add = parse_add(&["--preset", "cli", "serde", "-F", "clap:env"]).unwrap();
+
+        let clap = crates_to_add
+            .iter()
+            .find(|crate_to_add| crate_to_add.crate_name == "clap")
+            .unwrap();
+
+        assert_eq!(
+            clap.features,
+            Some(vec!["derive".to_string(), "env".to_string()])
+        );
+        assert!(crates_to_add
+            .iter()
+            .any(|crate_to_add| crate_to_add.crate_name == "serde"));
+    }
+
+    #[test]
+    fn profile_files_can_be_shared() {
+        let dir = test_dir("shared-profile");
+        let path = dir.join("api.toml");
+
+        std::fs::write(
+            &path,
+            r#"
+            name = "api"
+            crates = ["anyhow", { name = "tokio", features = ["full"] }]
+            "#,
+        )
+        .unwrap();
+
+        let crates_to_add = parse_add(&["--profile", path.to_str().unwrap(), "tokio@1.38"]);
+
+        std::fs::remove_dir_all(&dir).ok();
+
+        let crates_to_add = crates_to_add.unwrap();
+
+        assert_eq!(crates_to_add[0], CrateToAdd::new("anyhow"));
+        assert_eq!(crates_to_add[1].version.as_deref(), Some("1.38"));
+        assert_eq!(crates_to_add[1].features, Some(vec!["full".to_string()]));
+        assert!(parse_add(&["--profile", "does-not-exist"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn categories_are_found_ignoring_case_and_punctuation() {
+        let fixture = CatalogSource::File(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/crates.json").into(),
+        );
+
+        let categories = load_categories(&fixture, &[], Some("math")).await.unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0].name, "Math / Scientific");
+
+        assert!(load_categories(&fixture, &[], Some("does-not-exist"))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn features_of_crates_not_being_added_are_an_error() {
+        assert!(parse_add(&["serde", "-F", "tokio:full"]).is_err());
+        assert!(parse_add(&["serde", "-F", "derive"]).is_err());
+        assert!(parse_add(&["--preset", "does-not-exist"]).is_err());
+        assert!(parse_add(&["serde", "--no-default-features", "tokio"]).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+/// This module's job is to read the user's settings from `<config dir>/get-blessed/config.toml`
+use std::{error::Error, fs, path::PathBuf};
+
+use serde::Deserialize;
+
+use crate::{preset::Preset, scraper::CatalogSource};
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct Config {
+    ///Where to read the crates list from, it is overriden by the `--source` flag
+    pub source: Option<CatalogSource>,
+    ///Crates lists curated by the user's team, merged with the one from blessed.rs
+    pub team_catalogs: Vec<PathBuf>,
+    ///Bundles of crates of the user, shown along the bundled ones
+    pub presets: Vec<Preset>,
+}
+
+pub fn config_file() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("get-blessed").join("config.toml"))
+}
+
+impl Config {
+    ///Reads the config file, not having one is the same as using the default settings
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let Some(path) = config_file().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        let contents = fs::read_to_string(&path)?;
+
+        toml::from_str(&contents)
+            .map_err(|e| format!("Invalid config file {}: {e}", path.display()).into())
+    }
+}
@@ -1,15 +1,42 @@
-use crate::backend::{Categories, CategoriesWithSubCategories, Crates, Table, TableEntry};
-use crate::scraper::Group;
-
+use crate::backend::{Category, Crates, Table, TableEntry};
+use crate::scraper::{Group, Purpose};
 
 pub mod jsoncontentparser;
 
 pub trait ContentParser {
-    fn get_general_crates(&self) -> Table;
+    ///All the categories in the same order as blessed.rs shows them
+    fn get_categories(&self) -> Vec<Category>;
 
-    fn get_crates(&self, category: &Categories) -> Table;
+    fn get_crates(&self, category_name: &str) -> Option<Table>;
+}
 
-    fn get_crates_with_sub(&self, category: &CategoriesWithSubCategories) -> Table;
+impl TableEntry {
+    fn new(use_case: String, purpose: &Purpose) -> Self {
+        Self {
+            use_case,
+            crates: purpose
+                .recommendations
+                .iter()
+                .map(|recommendation| {
+                    let description = recommendation
+                        .notes
+                        .clone()
+                        .unwrap_or("No description".to_string());
+
+                    Crates {
+                        name: recommendation.name.clone(),
+                        description: match &recommendation.team_notes {
+                            Some(team_notes) => format!("Team: {team_notes}\n{description}"),
+                            None => description,
+                        },
+                        features: None,
+                        curation: recommendation.team_status.into(),
+                        registry: recommendation.registry.clone(),
+                    }
+                })
+                .collect(),
+        }
+    }
 }
 
 impl From<&Group> for Table {
@@ -19,52 +46,21 @@ impl From<&Group> for Table {
         //This means this is parsing a category with 1 table
         if let Some(purposes) = &value.purposes {
             for purpose in purposes {
-                let mut crates: Vec<Crates> = Vec::new();
-
-                for recommendation in &purpose.recommendations {
-                    crates.push(Crates {
-                        name: recommendation.name.clone(),
-                        description: recommendation
-                            .notes
-                            .clone()
-                            .unwrap_or("No description".to_string()),
-                        features: None,
-                    });
-                }
-
-                entries.push(TableEntry {
-                    use_case: String::default(),
-                    crates,
-                });
+                entries.push(TableEntry::new(purpose.name.clone(), purpose));
             }
+        }
 
-            return Table { entries };
-        };
-
-        //Parsing a category with multiple tables
+        //Parsing a category with multiple tables, the use case says which table it comes from
         if let Some(subgroups) = &value.subgroups {
             for subgroup in subgroups {
-                let mut crates: Vec<Crates> = Vec::new();
-                if let Some(purposes) = &subgroup.purposes {
-                    for purpose in purposes {
-                        purpose.recommendations.iter().for_each(|recommendation| {
-                            crates.push(Crates {
-                                name: recommendation.name.clone(),
-                                description: recommendation
-                                    .notes
-                                    .clone()
-                                    .unwrap_or("No description".to_string()),
-                                features: None,
-                            });
-                        });
-                    }
+                for purpose in subgroup.purposes.iter().flatten() {
+                    entries.push(TableEntry::new(
+                        format!("{} › {}", subgroup.name, purpose.name),
+                        purpose,
+                    ));
                 }
-                entries.push(TableEntry {
-                    use_case: String::default(),
-                    crates,
-                });
             }
-        };
+        }
         Table { entries }
     }
 }
@@ -72,12 +68,15 @@ impl From<&Group> for Table {
 #[cfg(test)]
 mod test {
     use self::jsoncontentparser::JsonContentParser;
+    use crate::scraper::{load_crates_data, CatalogSource, CratesData, Recommendation};
 
     use super::*;
 
-
     async fn setup_json_content_parser() -> JsonContentParser {
-        JsonContentParser::parse_content().await
+        let fixture = CatalogSource::File(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/crates.json").into(),
+        );
+        JsonContentParser::parse_content(&load_crates_data(&fixture).await.unwrap())
     }
 
     fn test_section(entries: &[TableEntry], name_first_recommended_crate: &str) {
@@ -91,77 +90,142 @@ mod test {
         assert_eq!(first_recommended_crate.name, name_first_recommended_crate);
     }
 
+    #[tokio::test]
+    async fn categories_keep_the_order_of_blessed_rs() {
+        let json_content_parser = setup_json_content_parser().await;
+        let categories: Vec<String> = json_content_parser
+            .get_categories()
+            .into_iter()
+            .map(|category| category.name)
+            .collect();
+
+        assert_eq!(
+            categories,
+            vec![
+                "General",
+                "Common",
+                "Math / Scientific",
+                "FFI / Interop",
+                "Cryptography",
+                "Networking",
+                "Databases",
+                "CLIs",
+                "Concurrency",
+                "Graphics"
+            ]
+        );
+    }
+
+    #[test]
+    fn new_upstream_groups_are_not_dropped() {
+        let crates_data = CratesData {
+            crate_groups: vec![Group {
+                name: "Embedded".to_string(),
+                subgroups: None,
+                purposes: Some(vec![Purpose {
+                    name: "HAL".to_string(),
+                    recommendations: vec![Recommendation {
+                        name: "embedded-hal".to_string(),
+                        notes: None,
+                        ..Recommendation::default()
+                    }],
+                }]),
+            }],
+        };
+
+        let json_content_parser = JsonContentParser::parse_content(&crates_data);
+        let section = json_content_parser.get_crates("Embedded").unwrap();
+        test_section(&section.entries, "embedded-hal");
+    }
+
+    #[tokio::test]
+    async fn crates_keep_the_use_case_they_are_recommended_for() {
+        let json_content_parser = setup_json_content_parser().await;
+        let use_cases: Vec<String> = json_content_parser
+            .get_crates("Common")
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.use_case)
+            .collect();
+
+        assert_eq!(
+            use_cases,
+            vec![
+                "Error Handling › For applications",
+                "Error Handling › For libraries",
+                "Logging › Text-based logging"
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn general_crates_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_general_crates();
+        let section = json_content_parser.get_crates("General").unwrap();
         test_section(&section.entries, "rand");
     }
 
     #[tokio::test]
     async fn common_crates_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Common);
+        let section = json_content_parser.get_crates("Common").unwrap();
         test_section(&section.entries, "anyhow");
     }
 
     #[tokio::test]
     async fn math_section_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_crates(&Categories::Math);
+        let section = json_content_parser.get_crates("Math / Scientific").unwrap();
         test_section(&section.entries, "num-traits");
     }
 
     #[tokio::test]
     async fn ffi_section_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_crates(&Categories::FFI);
+        let section = json_content_parser.get_crates("FFI / Interop").unwrap();
         test_section(&section.entries, "bindgen");
     }
 
     #[tokio::test]
     async fn cryptography_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_crates(&Categories::Cryptography);
+        let section = json_content_parser.get_crates("Cryptography").unwrap();
         test_section(&section.entries, "argon2");
     }
 
     #[tokio::test]
     async fn concurrency_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section =
-            json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Concurrency);
+        let section = json_content_parser.get_crates("Concurrency").unwrap();
         test_section(&section.entries, "parking_lot");
     }
 
     #[tokio::test]
     async fn networking_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section =
-            json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Networking);
+        let section = json_content_parser.get_crates("Networking").unwrap();
         test_section(&section.entries, "tokio");
     }
 
     #[tokio::test]
     async fn databases_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section =
-            json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Databases);
+        let section = json_content_parser.get_crates("Databases").unwrap();
         test_section(&section.entries, "sqlx");
     }
 
     #[tokio::test]
     async fn cli_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section = json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Clis);
+        let section = json_content_parser.get_crates("CLIs").unwrap();
         test_section(&section.entries, "clap");
     }
 
     #[tokio::test]
     async fn graphics_has_expected_data() {
         let json_content_parser = setup_json_content_parser().await;
-        let section =
-            json_content_parser.get_crates_with_sub(&CategoriesWithSubCategories::Graphics);
+        let section = json_content_parser.get_crates("Graphics").unwrap();
         test_section(&section.entries, "gtk4");
     }
 }
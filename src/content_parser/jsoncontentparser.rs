@@ -1,114 +1,64 @@
 use crate::{
-    backend::{Categories, CategoriesWithSubCategories, Table},
-    scraper::{scrape_site, Group},
+    backend::{Category, Table},
+    scraper::{CratesData, Group},
 };
 
 use super::ContentParser;
 
-#[allow(clippy::struct_field_names)]
 #[derive(Debug)]
 pub struct JsonContentParser {
-    general_crates: Table,
-    math_crates: Table,
-    ffi_crates: Table,
-    cryptography_crates: Table,
-    common_crates: Table,
-    concurrency_crates: Table,
-    networking_crates: Table,
-    database_crates: Table,
-    clis_crates: Table,
-    graphics_crates: Table,
+    categories: Vec<Category>,
 }
 
 impl JsonContentParser {
-    pub async fn parse_content() -> Self {
-        let page_content = scrape_site().await.unwrap();
-
-        let mut general_crates = Table::default();
-        let mut math_crates = Table::default();
-        let mut ffi_crates = Table::default();
-        let mut cryptography_crates = Table::default();
-        let mut common_crates = Table::default();
-        let mut concurrency_crates = Table::default();
-        let mut networking_crates = Table::default();
-        let mut database_crates = Table::default();
-        let mut clis_crates = Table::default();
-        let mut graphics_crates = Table::default();
+    pub fn parse_content(page_content: &CratesData) -> Self {
+        let mut categories: Vec<Category> = Vec::new();
 
         for group in &page_content.crate_groups {
-            match group.name.trim().to_lowercase().as_str() {
-                "common" => {
-                    //extract general table
-                    //the rest belongs to common crates , ignoring general
-                    let mut common_table = Group::default();
-
-                    let mut general_table = group.subgroups.as_ref().unwrap().iter();
+            if group.name.trim().to_lowercase() == "common" {
+                //extract general table as its own category
+                //the rest belongs to common crates , ignoring general
+                let (general_tables, common_tables): (Vec<Group>, Vec<Group>) = group
+                    .subgroups
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .partition(|sub| sub.name.trim().to_lowercase() == "general");
 
-                    general_crates = general_table
-                        .find(|sub| sub.name.trim().to_lowercase() == "general")
-                        .unwrap()
-                        .into();
+                for general_table in &general_tables {
+                    categories.push(Category::new(
+                        general_table.name.clone(),
+                        general_table.into(),
+                    ));
+                }
 
-                    let new_subgropus: Vec<Group> = group
-                        .subgroups
-                        .as_ref()
-                        .unwrap()
-                        .iter()
-                        .filter(|gr| gr.name.trim().to_lowercase() != "general")
-                        .cloned()
-                        .collect();
+                let common_table = Group {
+                    subgroups: Some(common_tables),
+                    ..group.clone()
+                };
 
-                    common_table.subgroups = Some(new_subgropus);
-                    common_crates = Into::into(&common_table);
-                }
-                "math / scientific" => math_crates = group.into(),
-                "ffi / interop" => ffi_crates = group.into(),
-                "cryptography" => cryptography_crates = group.into(),
-                "networking" => networking_crates = group.into(),
-                "databases" => database_crates = group.into(),
-                "clis" => clis_crates = group.into(),
-                "concurrency" => concurrency_crates = group.into(),
-                "graphics" => graphics_crates = group.into(),
-                _ => {}
+                categories.push(Category::new(group.name.clone(), (&common_table).into()));
+            } else {
+                categories.push(Category::new(group.name.clone(), group.into()));
             }
         }
 
-        Self {
-            general_crates,
-            math_crates,
-            ffi_crates,
-            cryptography_crates,
-            common_crates,
-            concurrency_crates,
-            networking_crates,
-            database_crates,
-            clis_crates,
-            graphics_crates,
-        }
+        // A group without crates would only be an empty tab
+        categories.retain(|category| !category.table.is_empty());
+
+        Self { categories }
     }
 }
 
 impl ContentParser for JsonContentParser {
-    fn get_general_crates(&self) -> Table {
-        self.general_crates.clone()
+    fn get_categories(&self) -> Vec<Category> {
+        self.categories.clone()
     }
 
-    fn get_crates(&self, category: &Categories) -> Table {
-        match category {
-            Categories::FFI => self.ffi_crates.clone(),
-            Categories::Math => self.math_crates.clone(),
-            Categories::Cryptography => self.cryptography_crates.clone(),
-        }
-    }
-
-    fn get_crates_with_sub(&self, category: &CategoriesWithSubCategories) -> Table {
-        match category {
-            CategoriesWithSubCategories::Clis => self.clis_crates.clone(),
-            CategoriesWithSubCategories::Common => self.common_crates.clone(),
-            CategoriesWithSubCategories::Graphics => self.graphics_crates.clone(),
-            CategoriesWithSubCategories::Databases => self.database_crates.clone(),
-            CategoriesWithSubCategories::Networking => self.networking_crates.clone(),
-            CategoriesWithSubCategories::Concurrency => self.concurrency_crates.clone(),
-        }
+    fn get_crates(&self, category_name: &str) -> Option<Table> {
+        self.categories
+            .iter()
+            .find(|category| category.name == category_name)
+            .map(|category| category.table.clone())
     }
 }
@@ -0,0 +1,243 @@
+/// This module's job is to ask crates.io about the crates shown in the app. Answers are kept on
+/// disk so the app starts instantly, and requests wait in a queue so crates.io is never asked
+/// more often than its crawler policy allows
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use crates_io_api::CrateResponse;
+use reqwest::{
+    header::{ETAG, IF_NONE_MATCH, RETRY_AFTER},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Mutex, Semaphore},
+    time::{sleep_until, Instant},
+};
+
+use crate::cache::{hash_key, write_atomically};
+
+const CRATES_IO_API_URL: &str = "https://crates.io/api/v1/crates";
+
+///Answers younger than this are used without asking crates.io again
+const CACHE_TTL: TimeDelta = TimeDelta::hours(12);
+
+///crates.io asks crawlers for at most one request per second
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+///How many requests can be sent or waiting for their turn at once, the rest wait for one of
+///them to finish
+const MAX_CONCURRENT_REQUESTS: usize = 2;
+
+///How long to wait when crates.io answers `429 Too Many Requests` without saying
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// What crates.io answered about a crate, as it was saved the last time it was fetched
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedCrate {
+    pub fetched_at: DateTime<Utc>,
+    ///Sent back to crates.io so it only answers again if the crate changed
+    pub etag: Option<String>,
+    pub response: CrateResponse,
+}
+
+impl CachedCrate {
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.fetched_at < CACHE_TTL
+    }
+}
+
+#[derive(Clone)]
+pub struct CratesIoClient {
+    client: reqwest::Client,
+    ///`None` if there is no cache directory, then every answer comes from crates.io
+    cache_dir: Option<PathBuf>,
+    ///Every request waits for its turn here, crates.io can push it back with `Retry-After`
+    next_request_at: Arc<Mutex<Instant>>,
+    in_flight: Arc<Semaphore>,
+}
+
+///crates.io's crawler policy asks every client to say who it is
+pub fn user_agent() -> String {
+    format!(
+        "get-blessed/v{} ({} {} {})",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::FAMILY,
+        env::consts::OS,
+        env::consts::ARCH
+    )
+}
+
+impl CratesIoClient {
+    pub fn new() -> Result<Self, reqwest::Error> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .user_agent(user_agent())
+                .build()?,
+            cache_dir: dirs::cache_dir().map(|dir| dir.join("get-blessed").join("crates-io")),
+            next_request_at: Arc::new(Mutex::new(Instant::now())),
+            in_flight: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+        })
+    }
+
+    ///For the requests to crates.io outside its API, like downloading a package, which do not
+    ///wait in the queue
+    pub fn http(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub fn cached(&self, crate_name: &str) -> Option<CachedCrate> {
+        load_crate_from(&self.cache_dir.as_ref()?.join(cache_file_name(crate_name)))
+    }
+
+    ///Asks crates.io about the crate unless `cached` is still the latest answer, then it returns
+    ///`None`
+    pub async fn fetch(
+        &self,
+        crate_name: &str,
+        cached: Option<&CachedCrate>,
+    ) -> Result<Option<CrateResponse>, Box<dyn Error + Send + Sync>> {
+        let _permit = self.in_flight.acquire().await?;
+
+        loop {
+            self.wait_turn().await;
+
+            let mut request = self.client.get(format!("{CRATES_IO_API_URL}/{crate_name}"));
+
+            if let Some(etag) = cached.and_then(|cached| cached.etag.as_ref()) {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+
+            let response = request.send().await?;
+
+            match response.status() {
+                StatusCode::TOO_MANY_REQUESTS => {
+                    let retry_after = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok()?.parse().ok())
+                        .map_or(DEFAULT_RETRY_AFTER, Duration::from_secs);
+
+                    *self.next_request_at.lock().await = Instant::now() + retry_after;
+                }
+                StatusCode::NOT_MODIFIED => {
+                    if let Some(cached) = cached {
+                        self.save(crate_name, cached.etag.clone(), &cached.response);
+                    }
+                    return Ok(None);
+                }
+                _ => {
+                    let response = response.error_for_status()?;
+
+                    let etag = response
+                        .headers()
+                        .get(ETAG)
+                        .and_then(|value| value.to_str().ok())
+                        .map(ToString::to_string);
+
+                    let information: CrateResponse = response.json().await?;
+
+                    self.save(crate_name, etag, &information);
+
+                    return Ok(Some(information));
+                }
+            }
+        }
+    }
+
+    ///Requests start at least `REQUEST_INTERVAL` apart
+    async fn wait_turn(&self) {
+        let mut next_request_at = self.next_request_at.lock().await;
+        sleep_until(*next_request_at).await;
+        *next_request_at = Instant::now() + REQUEST_INTERVAL;
+    }
+
+    ///The cache only saves requests, the app works the same without it
+    fn save(&self, crate_name: &str, etag: Option<String>, response: &CrateResponse) {
+        if let Some(cache_dir) = &self.cache_dir {
+            let cached = CachedCrate {
+                fetched_at: Utc::now(),
+                etag,
+                response: response.clone(),
+            };
+
+            save_crate_to(&cache_dir.join(cache_file_name(crate_name)), &cached).ok();
+        }
+    }
+}
+
+///Team catalogs can list any name, only the ones which are valid crate names are used as they are
+fn cache_file_name(crate_name: &str) -> String {
+    let is_crate_name = !crate_name.is_empty()
+        && crate_name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character));
+
+    if is_crate_name {
+        format!("{crate_name}.json")
+    } else {
+        format!("crate-{}.json", hash_key(crate_name))
+    }
+}
+
+fn load_crate_from(path: &Path) -> Option<CachedCrate> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn save_crate_to(path: &Path, cached: &CachedCrate) -> io::Result<()> {
+    write_atomically(path, serde_json::to_string(cached)?.as_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn saved_crate_can_be_loaded_back() {
+        let response: CrateResponse = serde_json::from_str(include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/crates_io_anyhow.json"
+        )))
+        .unwrap();
+
+        let path = test_dir("crates-io").join(cache_file_name("anyhow"));
+
+        let cached = CachedCrate {
+            fetched_at: Utc::now() - TimeDelta::days(1),
+            etag: Some("W/\"abc\"".to_string()),
+            response,
+        };
+
+        save_crate_to(&path, &cached).unwrap();
+
+        let loaded = load_crate_from(&path).unwrap();
+
+        fs::remove_dir_all(path.parent().unwrap()).ok();
+
+        assert_eq!(loaded.etag, cached.etag);
+        assert_eq!(loaded.response.crate_data.name, "anyhow");
+        assert_eq!(loaded.response.versions.len(), 1);
+        assert!(!loaded.is_fresh());
+    }
+
+    #[test]
+    fn names_which_are_not_crate_names_are_hashed() {
+        assert_eq!(cache_file_name("serde_json"), "serde_json.json");
+
+        for name in ["../../etc/passwd", "my crate", "café"] {
+            let file_name = cache_file_name(name);
+            assert!(file_name.starts_with("crate-"));
+            assert!(!file_name.contains(['/', ' ']));
+        }
+    }
+}
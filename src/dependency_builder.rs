@@ -1,22 +1,155 @@
 /// This module's job is to add de crate or dependencies to the user's project
-use std::fmt::Write;
-use std::{io, process::Command};
-use crate::view::widgets::{CrateItemList, ItemListStatus};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
 
+use serde::{Deserialize, Serialize};
+use toml_edit::{DocumentMut, Item};
+
+use crate::{
+    view::widgets::{CrateItemList, ItemListStatus},
+    workspace::{add_workspace_dependency, Workspace},
+};
+
+///The table of Cargo.toml the crate is added to
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyKind {
+    #[default]
+    Normal,
+    Dev,
+    Build,
+}
+
+impl DependencyKind {
+    pub fn table(self) -> &'static str {
+        match self {
+            Self::Normal => "dependencies",
+            Self::Dev => "dev-dependencies",
+            Self::Build => "build-dependencies",
+        }
+    }
+
+    fn cargo_add_flag(self) -> Option<&'static str> {
+        match self {
+            Self::Normal => None,
+            Self::Dev => Some("--dev"),
+            Self::Build => Some("--build"),
+        }
+    }
+
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Normal => Self::Dev,
+            Self::Dev => Self::Build,
+            Self::Build => Self::Normal,
+        }
+    }
+
+    ///Testing and benchmarking crates are usually only needed by tests, and crates which compile
+    ///or generate code by build scripts
+    pub fn suggested(category: &str, use_case: &str, crate_name: &str) -> Self {
+        const DEV_WORDS: [&str; 6] = ["test", "bench", "mock", "snapshot", "fuzz", "assert"];
+        const BUILD_CRATES: [&str; 8] = [
+            "cc",
+            "cmake",
+            "pkg-config",
+            "vcpkg",
+            "bindgen",
+            "cbindgen",
+            "autocfg",
+            "rustc_version",
+        ];
+
+        let context = format!("{category} {use_case}").to_lowercase();
+
+        // Whole words only, `fastest` is not about testing
+        let is_dev = context
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| DEV_WORDS.iter().any(|dev_word| word.starts_with(dev_word)));
+
+        if is_dev {
+            Self::Dev
+        } else if context.contains("build script") || BUILD_CRATES.contains(&crate_name) {
+            Self::Build
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+///What happens to the crate in the user's Cargo.toml
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    #[default]
+    Add,
+    ///The crate is already a dependency, `cargo add` adds the new features and version and the
+    ///features the user unselected are then taken out of its entry
+    Update { removed_features: Vec<String> },
+    ///`cargo remove`
+    Remove,
+}
 
 /// This is the data neccesary to add a crate to the user's project
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CrateToAdd {
     pub crate_name: String,
     pub features: Option<Vec<String>>,
+    ///`false` adds the crate with `--no-default-features`
+    pub default_features: bool,
+    ///A version requirement e.g. `1.0.200` or `=1.0.200`, `None` lets cargo pick the latest
+    pub version: Option<String>,
+    pub kind: DependencyKind,
+    pub change: Change,
+    ///The registry of `.cargo/config.toml` the crate comes from, `None` for crates.io
+    pub registry: Option<String>,
+    ///The key of Cargo.toml when the project renamed the crate, e.g. `json` for
+    ///`json = { package = "serde_json" }`
+    pub rename: Option<String>,
 }
 
+impl CrateToAdd {
+    pub fn new(crate_name: impl Into<String>) -> Self {
+        Self {
+            crate_name: crate_name.into(),
+            features: None,
+            default_features: true,
+            version: None,
+            kind: DependencyKind::Normal,
+            change: Change::Add,
+            registry: None,
+            rename: None,
+        }
+    }
+
+    ///What cargo knows the dependency by in Cargo.toml
+    pub fn manifest_key(&self) -> &str {
+        self.rename.as_deref().unwrap_or(&self.crate_name)
+    }
+}
 
 impl From<CrateItemList> for CrateToAdd {
     fn from(value: CrateItemList) -> Self {
+        Self::from(&value)
+    }
+}
+
+impl From<&CrateItemList> for CrateToAdd {
+    fn from(value: &CrateItemList) -> Self {
+        let change = match (&value.existing, &value.status) {
+            (None, _) => Change::Add,
+            (Some(_), ItemListStatus::Unselected) => Change::Remove,
+            (Some(_), ItemListStatus::Selected) => Change::Update {
+                removed_features: value.removed_features(),
+            },
+        };
+
         Self {
-            crate_name: value.name,
-            features: value.features.map(|features| {
+            crate_name: value.name.clone(),
+            features: value.features.as_ref().map(|features| {
                 features
                     .iter()
                     .filter_map(|feature_item| {
@@ -28,64 +161,1026 @@ impl From<CrateItemList> for CrateToAdd {
                     })
                     .collect()
             }),
+            default_features: value.default_features,
+            version: value.version.clone(),
+            kind: value.kind,
+            change,
+            registry: value.registry.clone(),
+            rename: value
+                .existing
+                .as_ref()
+                .map(|existing| existing.key.clone())
+                .filter(|key| *key != value.name),
         }
     }
 }
 
+/// What adding a crate would change in the user's Cargo.toml, as reported by `cargo add --dry-run`
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DependencyPreview {
+    ///The workspace package the crate is added to, `None` for the package of the current directory
+    pub package: Option<String>,
+    pub kind: DependencyKind,
+    pub crate_name: String,
+    ///The key the crate is written under when the project renamed it
+    pub rename: Option<String>,
+    pub registry: Option<String>,
+    pub version: Option<String>,
+    ///The features written in Cargo.toml, including the ones the crate already had
+    pub features: Vec<String>,
+    pub default_features: bool,
+    ///Every feature that ends up enabled, default ones included
+    pub enabled_features: Vec<String>,
+    ///How the crate is currently written in Cargo.toml, if it is already a dependency
+    pub current_entry: Option<String>,
+    ///The package's entry is `crate.workspace = true`
+    pub inherits_workspace: bool,
+    ///What is written to `[workspace.dependencies]`, if the crate is not there yet
+    pub workspace_entry: Option<String>,
+    ///The crate is taken out of Cargo.toml with `cargo remove`
+    pub is_removal: bool,
+    pub error: Option<String>,
+}
+
+impl DependencyPreview {
+    ///How the crate will be written in Cargo.toml
+    pub fn new_entry(&self) -> String {
+        let version = self.version.clone().unwrap_or("*".to_string());
 
+        let features: Vec<String> = self
+            .features
+            .iter()
+            .map(|feature| format!("\"{feature}\""))
+            .collect();
 
-impl From<&CrateItemList> for CrateToAdd {
-    fn from(value: &CrateItemList) -> Self {
-        Self {
-            crate_name: value.name.clone(),
-            features: value.features.as_ref().map(|features| {
+        let mut keys = Vec::new();
+
+        let key = self.rename.as_deref().unwrap_or(&self.crate_name);
+
+        // Cargo calls the crate by the key, `package` says which crate it really is
+        if key != self.crate_name {
+            keys.push(format!("package = \"{}\"", self.crate_name));
+        }
+
+        if self.inherits_workspace {
+            keys.push("workspace = true".to_string());
+        } else if keys.is_empty()
+            && features.is_empty()
+            && self.default_features
+            && self.registry.is_none()
+        {
+            return format!("{key} = \"{version}\"");
+        } else {
+            keys.push(format!("version = \"{version}\""));
+
+            if let Some(registry) = &self.registry {
+                keys.push(format!("registry = \"{registry}\""));
+            }
+
+            if !self.default_features {
+                keys.push("default-features = false".to_string());
+            }
+        }
+
+        if !features.is_empty() {
+            keys.push(format!("features = [{}]", features.join(", ")));
+        }
+
+        format!("{key} = {{ {} }}", keys.join(", "))
+    }
+
+    ///The Cargo.toml table the crate is written to, e.g. `[dev-dependencies]`
+    pub fn table_header(&self) -> String {
+        match &self.package {
+            Some(package) => format!("{package}: [{}]", self.kind.table()),
+            None => format!("[{}]", self.kind.table()),
+        }
+    }
+
+    fn from_dry_run(dependency: &CrateToAdd, manifest: Option<&toml::Table>, stderr: &str) -> Self {
+        let mut version = None;
+        let mut inherits_workspace = false;
+        let mut enabled_features = Vec::new();
+
+        for line in stderr.lines().map(str::trim) {
+            // e.g. `Adding serde v1.0.200 to dependencies` or `Adding serde =1.0.100 to dependencies`
+            // when the version was pinned and `Adding serde (workspace) to dependencies` when the
+            // crate is in `[workspace.dependencies]`
+            if let Some(added) = line.strip_prefix("Adding ") {
+                match added.split_whitespace().nth(1) {
+                    Some("(workspace)") => inherits_workspace = true,
+                    added_version => {
+                        version = added_version
+                            .map(|version| version.trim_start_matches('v').to_string());
+                    }
+                }
+            } else if let Some(feature) = line.strip_prefix("+ ") {
+                enabled_features.push(feature.to_string());
+            }
+        }
+
+        let current = manifest
+            .and_then(|manifest| manifest.get(dependency.kind.table()))
+            .and_then(|dependencies| dependencies.get(dependency.manifest_key()));
+
+        let mut features: Vec<String> = current
+            .and_then(|current| current.get("features"))
+            .and_then(toml::Value::as_array)
+            .map(|features| {
                 features
                     .iter()
-                    .filter_map(|feature_item| {
-                        if feature_item.status == ItemListStatus::Selected {
-                            Some(feature_item.name.clone())
-                        } else {
-                            None
-                        }
-                    })
+                    .filter_map(|feature| feature.as_str().map(ToString::to_string))
                     .collect()
-            }),
+            })
+            .unwrap_or_default();
+
+        // Without `--no-default-features` cargo add keeps what the entry already had
+        let default_features = dependency.default_features
+            && current
+                .and_then(|current| current.get("default-features"))
+                .and_then(toml::Value::as_bool)
+                .unwrap_or(true);
+
+        for feature in dependency.features.iter().flatten() {
+            if !features.contains(feature) {
+                features.push(feature.clone());
+            }
+        }
+
+        if let Change::Update { removed_features } = &dependency.change {
+            features.retain(|feature| !removed_features.contains(feature));
+            enabled_features.retain(|feature| !removed_features.contains(feature));
+        }
+
+        Self {
+            crate_name: dependency.crate_name.clone(),
+            rename: dependency.rename.clone(),
+            kind: dependency.kind,
+            registry: dependency.registry.clone(),
+            version,
+            features,
+            default_features,
+            enabled_features,
+            current_entry: current
+                .map(|current| format!("{} = {current}", dependency.manifest_key())),
+            inherits_workspace,
+            is_removal: dependency.change == Change::Remove,
+            ..Self::default()
         }
     }
 }
 
+///Finds the Cargo.toml `cargo add` would modify, the one of the package in the current directory
+pub fn manifest_path() -> io::Result<PathBuf> {
+    locate_project(false)
+}
+
+///The Cargo.toml of the workspace root, the package's one when it is not part of a workspace
+pub fn workspace_manifest_path() -> io::Result<PathBuf> {
+    locate_project(true)
+}
+
+fn locate_project(workspace: bool) -> io::Result<PathBuf> {
+    let mut command = Command::new("cargo");
+    command.args(["locate-project", "--message-format", "plain"]);
+
+    if workspace {
+        command.arg("--workspace");
+    }
+
+    let output = command.output()?;
+
+    if !output.status.success() {
+        return Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+///A copy of the manifests and Cargo.lock as they were before adding any crate
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ManifestBackup {
+    ///The contents are `None` for files which did not exist, like the Cargo.lock of a new project
+    files: Vec<(PathBuf, Option<String>)>,
+}
+
+impl ManifestBackup {
+    pub fn take(manifests: &[PathBuf]) -> io::Result<Self> {
+        let mut files = Vec::new();
+
+        for manifest in manifests {
+            files.push((manifest.clone(), Some(fs::read_to_string(manifest)?)));
+        }
+
+        // The lockfile lives next to the workspace's Cargo.toml, which may not be the package's one
+        let lockfile_path = locate_project(true)?.with_file_name("Cargo.lock");
+        let lockfile = fs::read_to_string(&lockfile_path).ok();
+        files.push((lockfile_path, lockfile));
 
+        Ok(Self { files })
+    }
+
+    pub fn restore(&self) -> io::Result<()> {
+        for (path, contents) in &self.files {
+            match contents {
+                Some(contents) => fs::write(path, contents)?,
+                None if path.exists() => fs::remove_file(path)?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+///What happened when running `cargo add` for one crate
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AddResult {
+    pub crate_name: String,
+    ///The workspace package the crate was added to
+    pub package: Option<String>,
+    ///What cargo printed to stderr when it could not add the crate
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AddReport {
+    pub results: Vec<AddResult>,
+    ///`None` when Cargo.toml could not be backed up, so it cannot be restored either
+    pub backup: Option<ManifestBackup>,
+}
+
+impl AddReport {
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|result| result.error.is_some())
+    }
+}
+
+///Leaves out the progress lines cargo prints before the error, like `Updating crates.io index`
+fn cargo_error(stderr: &str) -> String {
+    stderr
+        .split_once("error: ")
+        .map_or(stderr, |(_, error)| error)
+        .trim()
+        .to_string()
+}
+
+///`cargo add` only ever adds features, so the ones the user unselected are taken out of the
+///crate's entry. Features coming from `[workspace.dependencies]` are left as they are
+fn remove_features(
+    manifest: &Path,
+    kind: DependencyKind,
+    key: &str,
+    removed_features: &[String],
+) -> io::Result<()> {
+    let mut document: DocumentMut = fs::read_to_string(manifest)?
+        .parse()
+        .map_err(io::Error::other)?;
+
+    let Some(entry) = document
+        .get_mut(kind.table())
+        .and_then(|dependencies| dependencies.get_mut(key))
+        .and_then(Item::as_table_like_mut)
+    else {
+        return Ok(());
+    };
+
+    if let Some(features) = entry.get_mut("features").and_then(Item::as_array_mut) {
+        features.retain(|feature| {
+            feature
+                .as_str()
+                .is_none_or(|feature| !removed_features.iter().any(|removed| removed == feature))
+        });
+
+        features.fmt();
+
+        if features.is_empty() {
+            entry.remove("features");
+            entry.fmt();
+        }
+    }
+
+    fs::write(manifest, document.to_string())
+}
+
+///Where the crates go when the project is a workspace with more than one package
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WorkspaceTarget {
+    pub workspace: Workspace,
+    ///Every crate is added to each of these packages
+    pub packages: Vec<String>,
+    ///Write the versions to `[workspace.dependencies]` and `crate.workspace = true` to the packages
+    pub use_workspace_dependencies: bool,
+}
+
+#[derive(Clone)]
 pub struct DependenciesBuilder {
     crates_to_add: Vec<CrateToAdd>,
+    target: Option<WorkspaceTarget>,
 }
 
 impl DependenciesBuilder {
     pub fn new(crates_to_add: Vec<CrateToAdd>) -> Self {
-        Self { crates_to_add }
-    }
-
-    pub fn add_dependencies(&self) -> io::Result<()> {
-        for dependency in self.crates_to_add.clone() {
-            if let Some(features) = dependency.features {
-                let features: String =
-                    features.iter().fold(String::new(), |mut output, feature| {
-                        let _ = write!(output, " {feature} ");
-                        output
-                    });
-                Command::new("cargo")
-                    .arg("add")
-                    .arg(dependency.crate_name)
-                    .arg("-F")
-                    .arg(features)
-                    .output()?;
-            } else {
-                Command::new("cargo")
-                    .arg("add")
-                    .arg(dependency.crate_name)
-                    .arg("-q")
-                    .output()?;
+        Self {
+            crates_to_add,
+            target: None,
+        }
+    }
+
+    ///Adds the crates to packages of the workspace instead of the package of the current directory
+    pub fn target(mut self, target: Option<WorkspaceTarget>) -> Self {
+        self.target = target;
+        self
+    }
+
+    ///`None` stands for the package of the current directory
+    fn packages(&self) -> Vec<Option<&str>> {
+        match &self.target {
+            Some(target) => target
+                .packages
+                .iter()
+                .map(|name| Some(name.as_str()))
+                .collect(),
+            None => vec![None],
+        }
+    }
+
+    fn uses_workspace_dependencies(&self) -> bool {
+        self.target
+            .as_ref()
+            .is_some_and(|target| target.use_workspace_dependencies)
+    }
+
+    fn has_workspace_dependency(&self, crate_name: &str) -> bool {
+        self.target
+            .as_ref()
+            .and_then(|target| fs::read_to_string(&target.workspace.root_manifest).ok())
+            .and_then(|contents| contents.parse::<toml::Table>().ok())
+            .is_some_and(|manifest| {
+                manifest
+                    .get("workspace")
+                    .and_then(|workspace| workspace.get("dependencies"))
+                    .and_then(|dependencies| dependencies.get(crate_name))
+                    .is_some()
+            })
+    }
+
+    fn package_manifest(&self, package: Option<&str>) -> io::Result<PathBuf> {
+        match (package, &self.target) {
+            (Some(package), Some(target)) => target
+                .workspace
+                .member(package)
+                .map(|member| member.manifest_path.clone())
+                .ok_or_else(|| io::Error::other(format!("There is no package called {package}"))),
+            _ => manifest_path(),
+        }
+    }
+
+    ///Every manifest adding the crates can modify
+    fn manifests(&self) -> io::Result<Vec<PathBuf>> {
+        let mut manifests = self
+            .packages()
+            .into_iter()
+            .map(|package| self.package_manifest(package))
+            .collect::<io::Result<Vec<PathBuf>>>()?;
+
+        if let Some(target) = self
+            .target
+            .as_ref()
+            .filter(|_| self.uses_workspace_dependencies())
+        {
+            manifests.push(target.workspace.root_manifest.clone());
+        }
+
+        Ok(manifests)
+    }
+
+    ///With `inherit_workspace` the version and default features are left to
+    ///`[workspace.dependencies]`, cargo refuses to override them in the package
+    pub fn cargo_add(
+        dependency: &CrateToAdd,
+        package: Option<&str>,
+        inherit_workspace: bool,
+    ) -> Command {
+        let mut command = Command::new("cargo");
+        command.arg("add");
+
+        match &dependency.version {
+            Some(version) if !inherit_workspace => {
+                command.arg(format!("{}@{version}", dependency.crate_name))
             }
+            _ => command.arg(&dependency.crate_name),
+        };
+
+        if let Some(package) = package {
+            command.arg("--package").arg(package);
         }
-        Ok(())
+
+        if let Some(features) = dependency
+            .features
+            .as_ref()
+            .filter(|features| !features.is_empty())
+        {
+            command.arg("--features").arg(features.join(","));
+        }
+
+        if !dependency.default_features && !inherit_workspace {
+            command.arg("--no-default-features");
+        }
+
+        if let Some(registry) = dependency.registry.as_ref().filter(|_| !inherit_workspace) {
+            command.arg("--registry").arg(registry);
+        }
+
+        // Without it cargo writes a second entry next to the renamed one
+        if let Some(rename) = &dependency.rename {
+            command.arg("--rename").arg(rename);
+        }
+
+        if let Some(flag) = dependency.kind.cargo_add_flag() {
+            command.arg(flag);
+        }
+
+        command
+    }
+
+    fn cargo_remove(dependency: &CrateToAdd, package: Option<&str>) -> Command {
+        let mut command = Command::new("cargo");
+        command.arg("remove").arg(dependency.manifest_key());
+
+        if let Some(package) = package {
+            command.arg("--package").arg(package);
+        }
+
+        if let Some(flag) = dependency.kind.cargo_add_flag() {
+            command.arg(flag);
+        }
+
+        command
+    }
+
+    fn dry_run(&self, dependency: &CrateToAdd, package: Option<&str>) -> DependencyPreview {
+        let inherit_workspace =
+            self.uses_workspace_dependencies() && dependency.change != Change::Remove;
+
+        let manifest: Option<toml::Table> = self
+            .package_manifest(package)
+            .and_then(fs::read_to_string)
+            .ok()
+            .and_then(|contents| contents.parse().ok());
+
+        let mut command = match dependency.change {
+            Change::Remove => Self::cargo_remove(dependency, package),
+            Change::Add | Change::Update { .. } => {
+                Self::cargo_add(dependency, package, inherit_workspace)
+            }
+        };
+
+        // Until the crate is in `[workspace.dependencies]` cargo has to look for it in its registry
+        if let Some(registry) = dependency
+            .registry
+            .as_ref()
+            .filter(|_| inherit_workspace && !self.has_workspace_dependency(&dependency.crate_name))
+        {
+            command.arg("--registry").arg(registry);
+        }
+
+        let output = command.arg("--dry-run").output();
+
+        let mut preview = match output {
+            Ok(output) if output.status.success() => DependencyPreview::from_dry_run(
+                dependency,
+                manifest.as_ref(),
+                &String::from_utf8_lossy(&output.stderr),
+            ),
+            Ok(output) => DependencyPreview {
+                crate_name: dependency.crate_name.clone(),
+                kind: dependency.kind,
+                error: Some(cargo_error(&String::from_utf8_lossy(&output.stderr))),
+                ..DependencyPreview::default()
+            },
+            Err(e) => DependencyPreview {
+                crate_name: dependency.crate_name.clone(),
+                kind: dependency.kind,
+                error: Some(e.to_string()),
+                ..DependencyPreview::default()
+            },
+        };
+
+        preview.package = package.map(ToString::to_string);
+
+        // The crate is not in `[workspace.dependencies]` yet, cargo resolved its latest version
+        if inherit_workspace && preview.error.is_none() && !preview.inherits_workspace {
+            let version = dependency
+                .version
+                .clone()
+                .or(preview.version.clone())
+                .unwrap_or("*".to_string());
+
+            preview.workspace_entry = Some(
+                DependencyPreview {
+                    crate_name: dependency.crate_name.clone(),
+                    version: Some(version),
+                    registry: dependency.registry.clone(),
+                    default_features: dependency.default_features,
+                    ..DependencyPreview::default()
+                }
+                .new_entry(),
+            );
+            preview.inherits_workspace = true;
+            preview.default_features = true;
+        }
+
+        preview
+    }
+
+    fn add_dependency(&self, dependency: &CrateToAdd, package: Option<&str>) -> Option<String> {
+        let output = match &dependency.change {
+            Change::Remove => Self::cargo_remove(dependency, package).output(),
+            Change::Add | Change::Update { .. } => self.cargo_add_to_workspace(dependency, package),
+        };
+
+        let error = match output {
+            Ok(output) if output.status.success() => None,
+            Ok(output) => Some(cargo_error(&String::from_utf8_lossy(&output.stderr))),
+            Err(e) => Some(e.to_string()),
+        };
+
+        match &dependency.change {
+            Change::Update { removed_features }
+                if error.is_none() && !removed_features.is_empty() =>
+            {
+                self.package_manifest(package)
+                    .and_then(|manifest| {
+                        remove_features(
+                            &manifest,
+                            dependency.kind,
+                            dependency.manifest_key(),
+                            removed_features,
+                        )
+                    })
+                    .err()
+                    .map(|e| format!("Could not remove the unselected features: {e}"))
+            }
+            _ => error,
+        }
+    }
+
+    ///Writes the crate to `[workspace.dependencies]` first when the packages inherit it
+    fn cargo_add_to_workspace(
+        &self,
+        dependency: &CrateToAdd,
+        package: Option<&str>,
+    ) -> io::Result<std::process::Output> {
+        if let Some(target) = self
+            .target
+            .as_ref()
+            .filter(|_| self.uses_workspace_dependencies())
+        {
+            let preview = self.dry_run(dependency, package);
+
+            if let Some(e) = preview.error {
+                return Err(io::Error::other(e));
+            }
+
+            if preview.workspace_entry.is_some() {
+                let version = dependency
+                    .version
+                    .clone()
+                    .or(preview.version)
+                    .unwrap_or("*".to_string());
+
+                if let Err(e) = add_workspace_dependency(
+                    &target.workspace.root_manifest,
+                    &dependency.crate_name,
+                    &version,
+                    dependency.default_features,
+                    dependency.registry.as_deref(),
+                ) {
+                    return Err(io::Error::other(format!(
+                        "Could not write [workspace.dependencies]: {e}"
+                    )));
+                }
+            }
+        }
+
+        Self::cargo_add(dependency, package, self.uses_workspace_dependencies()).output()
+    }
+
+    /// Every crate is tried even if adding a previous one failed, the manifests are backed up
+    /// first so the changes can be undone
+    pub fn add_dependencies(&self) -> AddReport {
+        self.add_dependencies_after(self.backup())
+    }
+
+    ///`None` when the manifests could not be read
+    pub fn backup(&self) -> Option<ManifestBackup> {
+        self.manifests()
+            .and_then(|manifests| ManifestBackup::take(&manifests))
+            .ok()
+    }
+
+    ///Adds the crates once `backup` was taken, so the caller still has it if adding them is
+    ///interrupted
+    pub fn add_dependencies_after(&self, backup: Option<ManifestBackup>) -> AddReport {
+        let mut results = Vec::new();
+
+        for dependency in &self.crates_to_add {
+            for package in self.packages() {
+                results.push(AddResult {
+                    crate_name: dependency.crate_name.clone(),
+                    package: package.map(ToString::to_string),
+                    error: self.add_dependency(dependency, package),
+                });
+            }
+        }
+
+        AddReport { results, backup }
+    }
+
+    /// Runs `cargo add --dry-run` for every crate, nothing is written to Cargo.toml. The previews
+    /// are grouped by the package and table they are added to
+    pub fn preview(&self) -> Vec<DependencyPreview> {
+        let mut previews: Vec<DependencyPreview> = self
+            .crates_to_add
+            .iter()
+            .flat_map(|dependency| {
+                self.packages()
+                    .into_iter()
+                    .map(|package| self.dry_run(dependency, package))
+            })
+            .collect();
+
+        previews.sort_by(|a, b| (&a.package, a.kind).cmp(&(&b.package, b.kind)));
+
+        previews
+    }
+
+    ///Every crate failing with `error`, for when cargo could not even be run
+    pub fn failed_report(&self, error: &str, backup: Option<ManifestBackup>) -> AddReport {
+        let results = self
+            .crates_to_add
+            .iter()
+            .flat_map(|dependency| {
+                self.packages().into_iter().map(|package| AddResult {
+                    crate_name: dependency.crate_name.clone(),
+                    package: package.map(ToString::to_string),
+                    error: Some(error.to_string()),
+                })
+            })
+            .collect();
+
+        AddReport { results, backup }
+    }
+
+    pub fn failed_previews(&self, error: &str) -> Vec<DependencyPreview> {
+        self.crates_to_add
+            .iter()
+            .map(|dependency| DependencyPreview {
+                kind: dependency.kind,
+                crate_name: dependency.crate_name.clone(),
+                error: Some(error.to_string()),
+                ..DependencyPreview::default()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn preview_is_read_from_cargo_add_dry_run() {
+        let stderr = "    Updating crates.io index
+      Adding serde v1.0.200 to dependencies
+             Features:
+             + derive
+             + std
+             - alloc
+warning: aborting add due to dry run";
+
+        let manifest: toml::Table = r#"
+            [dependencies]
+            serde = { version = "1.0", features = ["rc"] }
+        "#
+        .parse()
+        .unwrap();
+
+        let preview = DependencyPreview::from_dry_run(
+            &CrateToAdd {
+                features: Some(vec!["derive".to_string()]),
+                ..CrateToAdd::new("serde")
+            },
+            Some(&manifest),
+            stderr,
+        );
+
+        assert_eq!(preview.version.as_deref(), Some("1.0.200"));
+        assert_eq!(preview.enabled_features, vec!["derive", "std"]);
+        assert_eq!(
+            preview.current_entry.as_deref(),
+            Some(r#"serde = { version = "1.0", features = ["rc"] }"#)
+        );
+        assert_eq!(
+            preview.new_entry(),
+            r#"serde = { version = "1.0.200", features = ["rc", "derive"] }"#
+        );
+    }
+
+    #[test]
+    fn restoring_the_backup_undoes_cargo_add() {
+        let project = test_dir("backup");
+
+        let (manifest_path, lockfile_path) =
+            (project.join("Cargo.toml"), project.join("Cargo.lock"));
+
+        let backup = ManifestBackup {
+            files: vec![
+                (manifest_path.clone(), Some("[dependencies]\n".to_string())),
+                (lockfile_path.clone(), None),
+            ],
+        };
+
+        fs::write(&manifest_path, "[dependencies]\nserde = \"1.0\"\n").unwrap();
+        fs::write(&lockfile_path, "version = 3").unwrap();
+
+        backup.restore().unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&manifest_path).unwrap(),
+            "[dependencies]\n"
+        );
+        assert!(!lockfile_path.exists());
+
+        fs::remove_dir_all(project).ok();
+    }
+
+    #[test]
+    fn interrupted_adding_fails_every_crate_and_keeps_the_backup() {
+        let backup = ManifestBackup {
+            files: vec![(
+                PathBuf::from("Cargo.toml"),
+                Some("[dependencies]\n".to_string()),
+            )],
+        };
+
+        let report =
+            DependenciesBuilder::new(vec![CrateToAdd::new("serde"), CrateToAdd::new("tokio")])
+                .failed_report("cargo add stopped", Some(backup.clone()));
+
+        assert_eq!(report.results.len(), 2);
+        assert!(report
+            .results
+            .iter()
+            .all(|result| result.error.as_deref() == Some("cargo add stopped")));
+        assert_eq!(report.backup, Some(backup));
+    }
+
+    #[test]
+    fn progress_lines_are_left_out_of_cargo_errors() {
+        let stderr = "    Updating crates.io index
+error: the crate `does-not-exist` could not be found in registry index.";
+
+        assert_eq!(
+            cargo_error(stderr),
+            "the crate `does-not-exist` could not be found in registry index."
+        );
+    }
+
+    #[test]
+    fn every_feature_is_passed_to_cargo_add() {
+        let command = DependenciesBuilder::cargo_add(
+            &CrateToAdd {
+                features: Some(vec!["full".to_string(), "tracing".to_string()]),
+                default_features: false,
+                ..CrateToAdd::new("tokio")
+            },
+            None,
+            false,
+        );
+
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(
+            args,
+            [
+                "add",
+                "tokio",
+                "--features",
+                "full,tracing",
+                "--no-default-features"
+            ]
+        );
+    }
+
+    #[test]
+    fn default_features_off_is_written_to_the_entry() {
+        let preview = DependencyPreview {
+            crate_name: "tokio".to_string(),
+            version: Some("1.0".to_string()),
+            default_features: false,
+            ..DependencyPreview::default()
+        };
+
+        assert_eq!(
+            preview.new_entry(),
+            r#"tokio = { version = "1.0", default-features = false }"#
+        );
+    }
+
+    #[test]
+    fn the_chosen_version_is_passed_to_cargo_add() {
+        let command = DependenciesBuilder::cargo_add(
+            &CrateToAdd {
+                version: Some("=1.0.100".to_string()),
+                ..CrateToAdd::new("serde")
+            },
+            None,
+            false,
+        );
+
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(args, ["add", "serde@=1.0.100"]);
+    }
+
+    #[test]
+    fn crates_of_other_registries_are_added_from_them() {
+        let command = DependenciesBuilder::cargo_add(
+            &CrateToAdd {
+                registry: Some("internal".to_string()),
+                ..CrateToAdd::new("company-logging")
+            },
+            None,
+            false,
+        );
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(args, ["add", "company-logging", "--registry", "internal"]);
+
+        let preview = DependencyPreview {
+            crate_name: "company-logging".to_string(),
+            version: Some("0.3.1".to_string()),
+            registry: Some("internal".to_string()),
+            default_features: true,
+            ..DependencyPreview::default()
+        };
+
+        assert_eq!(
+            preview.new_entry(),
+            r#"company-logging = { version = "0.3.1", registry = "internal" }"#
+        );
+    }
+
+    #[test]
+    fn testing_and_build_crates_are_not_normal_dependencies() {
+        assert_eq!(
+            DependencyKind::suggested("Tooling", "Testing › Snapshot testing", "insta"),
+            DependencyKind::Dev
+        );
+        assert_eq!(
+            DependencyKind::suggested("Common", "Fastest hashing", "ahash"),
+            DependencyKind::Normal
+        );
+        assert_eq!(
+            DependencyKind::suggested("FFI / Interop", "C", "bindgen"),
+            DependencyKind::Build
+        );
+
+        let command = DependenciesBuilder::cargo_add(
+            &CrateToAdd {
+                kind: DependencyKind::Dev,
+                ..CrateToAdd::new("insta")
+            },
+            None,
+            false,
+        );
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(args, ["add", "insta", "--dev"]);
+    }
+
+    #[test]
+    fn workspace_packages_inherit_the_version() {
+        let command = DependenciesBuilder::cargo_add(
+            &CrateToAdd {
+                version: Some("1.38".to_string()),
+                default_features: false,
+                features: Some(vec!["rt".to_string()]),
+                ..CrateToAdd::new("tokio")
+            },
+            Some("api"),
+            true,
+        );
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(
+            args,
+            ["add", "tokio", "--package", "api", "--features", "rt"]
+        );
+
+        let preview = DependencyPreview {
+            package: Some("api".to_string()),
+            crate_name: "tokio".to_string(),
+            features: vec!["rt".to_string()],
+            inherits_workspace: true,
+            ..DependencyPreview::default()
+        };
+
+        assert_eq!(
+            preview.new_entry(),
+            r#"tokio = { workspace = true, features = ["rt"] }"#
+        );
+        assert_eq!(preview.table_header(), "api: [dependencies]");
+    }
+
+    #[test]
+    fn renamed_dependencies_are_removed_and_updated_by_their_key() {
+        let renamed = CrateToAdd {
+            rename: Some("json".to_string()),
+            ..CrateToAdd::new("serde_json")
+        };
+
+        let update = DependenciesBuilder::cargo_add(&renamed, None, false);
+        let args: Vec<&std::ffi::OsStr> = update.get_args().collect();
+        assert_eq!(args, ["add", "serde_json", "--rename", "json"]);
+
+        let removal = DependenciesBuilder::cargo_remove(
+            &CrateToAdd {
+                change: Change::Remove,
+                ..renamed
+            },
+            None,
+        );
+        let args: Vec<&std::ffi::OsStr> = removal.get_args().collect();
+        assert_eq!(args, ["remove", "json"]);
+    }
+
+    #[test]
+    fn renamed_dependencies_are_previewed_with_their_package() {
+        let preview = DependencyPreview {
+            crate_name: "serde_json".to_string(),
+            rename: Some("json".to_string()),
+            version: Some("1".to_string()),
+            default_features: true,
+            ..DependencyPreview::default()
+        };
+
+        assert_eq!(
+            preview.new_entry(),
+            r#"json = { package = "serde_json", version = "1" }"#
+        );
+    }
+
+    #[test]
+    fn unselected_features_are_removed_from_the_entry() {
+        let dir = test_dir("remove-features");
+        let manifest = dir.join("Cargo.toml");
+
+        fs::write(
+            &manifest,
+            "[dependencies]\n# serialization\nserde = { version = \"1\", features = [\"rc\", \"derive\"] }\n\n[dev-dependencies]\ninsta = { version = \"1\", features = [\"json\"] }\n",
+        )
+        .unwrap();
+
+        remove_features(
+            &manifest,
+            DependencyKind::Normal,
+            "serde",
+            &["rc".to_string()],
+        )
+        .unwrap();
+        remove_features(
+            &manifest,
+            DependencyKind::Dev,
+            "insta",
+            &["json".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&manifest).unwrap(),
+            "[dependencies]\n# serialization\nserde = { version = \"1\", features = [\"derive\"] }\n\n[dev-dependencies]\ninsta = { version = \"1\" }\n"
+        );
+
+        fs::remove_dir_all(dir).ok();
+
+        let command = DependenciesBuilder::cargo_remove(
+            &CrateToAdd {
+                kind: DependencyKind::Dev,
+                change: Change::Remove,
+                ..CrateToAdd::new("insta")
+            },
+            Some("api"),
+        );
+        let args: Vec<&std::ffi::OsStr> = command.get_args().collect();
+
+        assert_eq!(args, ["remove", "insta", "--package", "api", "--dev"]);
     }
 }
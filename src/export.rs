@@ -0,0 +1,331 @@
+/// This module's job is to write the selected crates somewhere else than the project's Cargo.toml,
+/// as a `[dependencies]` snippet, a script of `cargo add` commands or json, to share them in a doc
+/// or a review
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    dependency_builder::{
+        Change, CrateToAdd, DependenciesBuilder, DependencyKind, DependencyPreview,
+    },
+    index::{latest_stable, IndexClient},
+    registry::Registries,
+};
+
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    ///`[dependencies]` tables to paste into Cargo.toml
+    #[default]
+    Toml,
+    ///A shell script running `cargo add` for every crate
+    Script,
+    ///An array with the version, features, kind and registry of every crate
+    Json,
+}
+
+impl ExportFormat {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Toml => Self::Script,
+            Self::Script => Self::Json,
+            Self::Json => Self::Toml,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "Cargo.toml",
+            Self::Script => "cargo add script",
+            Self::Json => "JSON",
+        }
+    }
+
+    ///The file written to the current directory from the terminal ui
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::Toml => "dependencies.toml",
+            Self::Script => "add-dependencies.sh",
+            Self::Json => "dependencies.json",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportedCrate<'a> {
+    name: &'a str,
+    version: Option<&'a str>,
+    features: &'a [String],
+    default_features: bool,
+    kind: DependencyKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<&'a str>,
+}
+
+///Looks up in their index the latest version of the crates without a version requirement which
+///`latest_versions` does not have yet. Returns why the ones not found could not be
+pub async fn find_latest_versions(
+    crates: &[CrateToAdd],
+    latest_versions: &mut BTreeMap<String, String>,
+    crates_io: &IndexClient,
+    registries: &Registries,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    let unknown: Vec<&CrateToAdd> = crates
+        .iter()
+        .filter(|krate| {
+            krate.version.is_none()
+                && krate.change != Change::Remove
+                && !latest_versions.contains_key(&krate.crate_name)
+        })
+        .collect();
+
+    for krate in unknown {
+        let index = match &krate.registry {
+            Some(registry) => registries.index_client(registry),
+            None => Some(crates_io.clone()),
+        };
+
+        let Some(index) = index else {
+            errors.push(format!(
+                "the registry of {} is not in .cargo/config.toml",
+                krate.crate_name
+            ));
+            continue;
+        };
+
+        match index.versions(&krate.crate_name).await {
+            Ok(versions) => {
+                if let Some(latest) = latest_stable(&versions) {
+                    latest_versions.insert(krate.crate_name.clone(), latest.num.clone());
+                }
+            }
+            Err(e) => errors.push(format!(
+                "could not get the latest version of {}: {e}",
+                krate.crate_name
+            )),
+        }
+    }
+
+    errors
+}
+
+///Crates being removed are left out, they only make sense in the project they are removed from.
+///`latest_versions` gives a version to the crates without a requirement, the script leaves it
+///to `cargo add` like adding them from the terminal ui does. Fails when a crate still has no
+///version
+pub fn render(
+    crates: &[CrateToAdd],
+    format: ExportFormat,
+    latest_versions: &BTreeMap<String, String>,
+) -> Result<String, String> {
+    let mut crates: Vec<CrateToAdd> = crates
+        .iter()
+        .filter(|krate| krate.change != Change::Remove)
+        .cloned()
+        .collect();
+
+    if format != ExportFormat::Script {
+        for krate in &mut crates {
+            if krate.version.is_none() {
+                krate.version = latest_versions.get(&krate.crate_name).cloned();
+            }
+        }
+
+        let unknown: Vec<&str> = crates
+            .iter()
+            .filter(|krate| krate.version.is_none())
+            .map(|krate| krate.crate_name.as_str())
+            .collect();
+
+        if !unknown.is_empty() {
+            return Err(format!(
+                "The latest version of {} is not known, give it a version to export it",
+                unknown.join(", ")
+            ));
+        }
+    }
+
+    Ok(match format {
+        ExportFormat::Toml => render_toml(&mut crates),
+        ExportFormat::Script => render_script(&crates),
+        ExportFormat::Json => render_json(&crates),
+    })
+}
+
+fn render_toml(crates: &mut [CrateToAdd]) -> String {
+    // Stable, so the crates of a table keep the order they were selected in
+    crates.sort_by_key(|krate| krate.kind);
+
+    let mut lines: Vec<String> = Vec::new();
+
+    for (index, krate) in crates.iter().enumerate() {
+        if index == 0 || crates[index - 1].kind != krate.kind {
+            if index > 0 {
+                lines.push(String::new());
+            }
+            lines.push(format!("[{}]", krate.kind.table()));
+        }
+
+        let entry = DependencyPreview {
+            kind: krate.kind,
+            crate_name: krate.crate_name.clone(),
+            rename: krate.rename.clone(),
+            registry: krate.registry.clone(),
+            version: krate.version.clone(),
+            features: krate.features.clone().unwrap_or_default(),
+            default_features: krate.default_features,
+            ..DependencyPreview::default()
+        }
+        .new_entry();
+
+        lines.push(entry);
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn render_script(crates: &[CrateToAdd]) -> String {
+    let commands: Vec<String> = crates
+        .iter()
+        .map(|krate| {
+            let command = DependenciesBuilder::cargo_add(krate, None, false);
+
+            let args: Vec<String> = command
+                .get_args()
+                .map(|arg| shell_quote(&arg.to_string_lossy()))
+                .collect();
+
+            format!("cargo {}", args.join(" "))
+        })
+        .collect();
+
+    format!("#!/bin/sh\nset -e\n\n{}\n", commands.join("\n"))
+}
+
+///Version requirements like `>=1.0` would otherwise be read as a redirection
+fn shell_quote(arg: &str) -> String {
+    let is_safe = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|character| character.is_alphanumeric() || "-_.,@=:/+".contains(character));
+
+    if is_safe {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+fn render_json(crates: &[CrateToAdd]) -> String {
+    let exported: Vec<ExportedCrate> = crates
+        .iter()
+        .map(|krate| ExportedCrate {
+            name: &krate.crate_name,
+            version: krate.version.as_deref(),
+            features: krate.features.as_deref().unwrap_or_default(),
+            default_features: krate.default_features,
+            kind: krate.kind,
+            registry: krate.registry.as_deref(),
+        })
+        .collect();
+
+    let mut json = serde_json::to_string_pretty(&exported).unwrap_or_default();
+    json.push('\n');
+    json
+}
+
+///The script is made executable so it can be run right away
+pub fn write_export(path: &Path, contents: &str, format: ExportFormat) -> io::Result<()> {
+    fs::write(path, contents)?;
+
+    #[cfg(unix)]
+    if format == ExportFormat::Script {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+
+    Ok(())
+}
+
+///Asks the terminal to put the text in the clipboard with the OSC 52 escape sequence, which also
+///works over ssh. Terminals which do not support it ignore it
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+    stdout.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn selection() -> Vec<CrateToAdd> {
+        vec![
+            CrateToAdd {
+                kind: DependencyKind::Dev,
+                ..CrateToAdd::new("insta")
+            },
+            CrateToAdd::new("anyhow"),
+            CrateToAdd {
+                version: Some(">=1.38".to_string()),
+                features: Some(vec!["full".to_string()]),
+                default_features: false,
+                ..CrateToAdd::new("tokio")
+            },
+            CrateToAdd {
+                change: Change::Remove,
+                ..CrateToAdd::new("log")
+            },
+        ]
+    }
+
+    #[test]
+    fn toml_snippet_is_grouped_by_table() {
+        let mut latest = BTreeMap::from([
+            ("anyhow".to_string(), "1.0.86".to_string()),
+            ("tokio".to_string(), "1.40.0".to_string()),
+        ]);
+
+        let unknown = render(&selection(), ExportFormat::Toml, &latest).unwrap_err();
+
+        latest.insert("insta".to_string(), "1.39.0".to_string());
+        let snippet = render(&selection(), ExportFormat::Toml, &latest).unwrap();
+
+        assert!(unknown.contains("insta"));
+        assert!(!unknown.contains("anyhow"));
+        assert_eq!(
+            snippet,
+            "[dependencies]\n\
+             anyhow = \"1.0.86\"\n\
+             tokio = { version = \">=1.38\", default-features = false, features = [\"full\"] }\n\
+             \n\
+             [dev-dependencies]\n\
+             insta = \"1.39.0\"\n"
+        );
+        assert!(snippet.parse::<toml::Table>().is_ok());
+    }
+
+    #[test]
+    fn script_runs_cargo_add_for_every_crate() {
+        let script = render(&selection(), ExportFormat::Script, &BTreeMap::new()).unwrap();
+
+        assert_eq!(
+            script,
+            "#!/bin/sh\nset -e\n\n\
+             cargo add insta --dev\n\
+             cargo add anyhow\n\
+             cargo add 'tokio@>=1.38' --features full --no-default-features\n"
+        );
+    }
+}
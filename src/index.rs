@@ -0,0 +1,286 @@
+/// This module's job is to read the versions and features of crates from a registry index, from
+/// its sparse index over the network or from the copy cargo keeps in `~/.cargo/registry/index`
+/// when offline
+use std::{cmp::Reverse, collections::HashMap, env, error::Error, fs, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use reqwest::header::AUTHORIZATION;
+use semver::Version;
+use serde::Deserialize;
+
+pub const CRATES_IO_INDEX_URL: &str = "sparse+https://index.crates.io";
+
+///One line of the index, a published version of the crate
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexVersion {
+    #[serde(rename = "vers")]
+    pub num: String,
+    #[serde(default)]
+    pub features: HashMap<String, Vec<String>>,
+    ///Features using the `dep:` and `crate?/feature` syntax, kept apart so old cargo versions
+    ///skip them
+    #[serde(default)]
+    features2: HashMap<String, Vec<String>>,
+    pub yanked: bool,
+    pub rust_version: Option<String>,
+    ///Only versions published since crates.io started recording it have it
+    pub pubtime: Option<DateTime<Utc>>,
+}
+
+impl IndexVersion {
+    pub fn new(
+        num: String,
+        features: HashMap<String, Vec<String>>,
+        yanked: bool,
+        rust_version: Option<String>,
+        pubtime: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            num,
+            features,
+            features2: HashMap::new(),
+            yanked,
+            rust_version,
+            pubtime,
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut version: Self = serde_json::from_str(line).ok()?;
+        let features2 = std::mem::take(&mut version.features2);
+        version.features.extend(features2);
+        Some(version)
+    }
+}
+
+///The version `cargo add` picks by default: the newest one that is neither yanked nor a
+///prerelease, or the newest prerelease when there are only prereleases. Yanked versions are
+///never picked. `versions` go from the newest to the oldest
+pub fn latest_stable(versions: &[IndexVersion]) -> Option<&IndexVersion> {
+    let mut available = versions.iter().filter(|version| !version.yanked);
+
+    available
+        .clone()
+        .find(|version| !version.num.contains('-'))
+        .or_else(|| available.next())
+}
+
+#[derive(Clone)]
+pub struct IndexClient {
+    client: reqwest::Client,
+    ///Like `https://index.crates.io`, without the `sparse+` cargo puts in front
+    index_url: String,
+    ///Git indexes are not served over http, only cargo's copy of them can be read
+    is_sparse: bool,
+    ///Sent to private registries
+    token: Option<String>,
+}
+
+impl IndexClient {
+    pub fn new(index_url: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            index_url: index_url
+                .trim_start_matches("sparse+")
+                .trim_end_matches('/')
+                .to_string(),
+            is_sparse: index_url.starts_with("sparse+"),
+            token: None,
+        }
+    }
+
+    pub fn token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    ///Every version of the crate, from the newest to the oldest. When the index can not be
+    ///reached the versions come from cargo's copy, which knows the crates it downloaded before
+    pub async fn versions(
+        &self,
+        crate_name: &str,
+    ) -> Result<Vec<IndexVersion>, Box<dyn Error + Send + Sync>> {
+        let mut versions = match self.fetch(crate_name).await {
+            Ok(versions) => versions,
+            Err(e) => self.read_cargo_cache(crate_name).ok_or(e)?,
+        };
+
+        sort_newest_first(&mut versions);
+
+        Ok(versions)
+    }
+
+    async fn fetch(
+        &self,
+        crate_name: &str,
+    ) -> Result<Vec<IndexVersion>, Box<dyn Error + Send + Sync>> {
+        if !self.is_sparse {
+            return Err(format!("{} is not a sparse index", self.index_url).into());
+        }
+
+        let path =
+            index_path(crate_name).ok_or_else(|| format!("{crate_name} is not a crate name"))?;
+
+        let mut request = self.client.get(format!("{}/{path}", self.index_url));
+
+        if let Some(token) = &self.token {
+            request = request.header(AUTHORIZATION, token);
+        }
+
+        let file = request.send().await?.error_for_status()?.text().await?;
+
+        Ok(file.lines().filter_map(IndexVersion::parse).collect())
+    }
+
+    fn read_cargo_cache(&self, crate_name: &str) -> Option<Vec<IndexVersion>> {
+        let path = index_path(crate_name)?;
+
+        let host = self
+            .index_url
+            .split("://")
+            .last()?
+            .split(['/', ':'])
+            .next()?;
+
+        let index_dir = cargo_home()?.join("registry").join("index");
+
+        // Cargo names the copy of an index after its host followed by a hash
+        fs::read_dir(index_dir)
+            .ok()?
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!("{host}-"))
+            })
+            .find_map(|entry| {
+                let cached = fs::read(entry.path().join(".cache").join(&path)).ok()?;
+                Some(parse_cargo_cache(&cached))
+            })
+            .filter(|versions| !versions.is_empty())
+    }
+}
+
+pub fn cargo_home() -> Option<PathBuf> {
+    env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cargo")))
+}
+
+///Where the index keeps a crate: `1/a`, `2/ab`, `3/a/abc` or `ab/cd/abcd...`. `None` for names
+///which can not be crate names, like the ones with other than ascii letters, numbers, `-` and `_`
+pub fn index_path(crate_name: &str) -> Option<String> {
+    let is_crate_name = !crate_name.is_empty()
+        && crate_name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character));
+
+    if !is_crate_name {
+        return None;
+    }
+
+    let name = crate_name.to_ascii_lowercase();
+
+    Some(match name.len() {
+        1 => format!("1/{name}"),
+        2 => format!("2/{name}"),
+        3 => format!("3/{}/{name}", &name[..1]),
+        _ => format!("{}/{}/{name}", &name[..2], &name[2..4]),
+    })
+}
+
+///Cargo's copy starts with a version byte, the index format as 4 bytes and the index version of
+///the file, then every version number is followed by its line of the index. Each part ends with a
+///nul byte
+fn parse_cargo_cache(cached: &[u8]) -> Vec<IndexVersion> {
+    cached
+        .get(5..)
+        .unwrap_or_default()
+        .split(|byte| *byte == 0)
+        .skip(2)
+        .step_by(2)
+        .filter_map(|line| IndexVersion::parse(&String::from_utf8_lossy(line)))
+        .collect()
+}
+
+///Orders versions by semver, so `1.10.0` comes after `1.9.0` and `1.0.0-rc.10` after
+///`1.0.0-rc.2`. Versions which are not semver go last
+fn sort_newest_first(versions: &mut [IndexVersion]) {
+    versions.sort_by_cached_key(|version| Reverse(Version::parse(&version.num).ok()));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crates_are_found_where_the_index_keeps_them() {
+        assert_eq!(index_path("a").as_deref(), Some("1/a"));
+        assert_eq!(index_path("cc").as_deref(), Some("2/cc"));
+        assert_eq!(index_path("syn").as_deref(), Some("3/s/syn"));
+        assert_eq!(index_path("Serde").as_deref(), Some("se/rd/serde"));
+        assert_eq!(index_path("çafé"), None);
+        assert_eq!(index_path("../etc"), None);
+    }
+
+    #[test]
+    fn cargo_cache_is_read_newest_first() {
+        let mut cached = vec![3, 2, 0, 0, 0];
+        cached.extend_from_slice(b"etag\0");
+        for line in [
+            r#"{"name":"demo","vers":"1.9.0","deps":[],"features":{"std":[]},"yanked":false}"#,
+            r#"{"name":"demo","vers":"1.10.0-rc.1","deps":[],"features":{},"yanked":false}"#,
+            r#"{"name":"demo","vers":"1.10.0","deps":[],"features":{"std":[]},"features2":{"serde":["dep:serde"]},"yanked":false,"rust_version":"1.70","pubtime":"2024-05-01T10:00:00Z"}"#,
+            r#"{"name":"demo","vers":"2.0.0","deps":[],"features":{},"yanked":true}"#,
+        ] {
+            let version: serde_json::Value = serde_json::from_str(line).unwrap();
+            cached.extend_from_slice(version["vers"].as_str().unwrap().as_bytes());
+            cached.push(0);
+            cached.extend_from_slice(line.as_bytes());
+            cached.push(0);
+        }
+
+        let mut versions = parse_cargo_cache(&cached);
+        sort_newest_first(&mut versions);
+
+        let nums: Vec<&str> = versions
+            .iter()
+            .map(|version| version.num.as_str())
+            .collect();
+        assert_eq!(nums, ["2.0.0", "1.10.0", "1.10.0-rc.1", "1.9.0"]);
+
+        let latest = latest_stable(&versions).unwrap();
+        assert_eq!(latest.num, "1.10.0");
+        assert_eq!(latest.features.len(), 2);
+        assert_eq!(latest.rust_version.as_deref(), Some("1.70"));
+        assert!(latest.pubtime.is_some());
+    }
+
+    #[test]
+    fn prereleases_are_ordered_by_semver_and_yanked_versions_are_never_latest() {
+        let version = |num: &str, yanked: bool| {
+            IndexVersion::new(num.to_string(), HashMap::new(), yanked, None, None)
+        };
+
+        let mut versions = vec![
+            version("1.0.0-rc.2", false),
+            version("1.0.0-rc.10", false),
+            version("not-semver", false),
+            version("1.0.0-rc.11", true),
+        ];
+        sort_newest_first(&mut versions);
+
+        let nums: Vec<&str> = versions
+            .iter()
+            .map(|version| version.num.as_str())
+            .collect();
+        assert_eq!(
+            nums,
+            ["1.0.0-rc.11", "1.0.0-rc.10", "1.0.0-rc.2", "not-semver"]
+        );
+
+        assert_eq!(latest_stable(&versions).unwrap().num, "1.0.0-rc.10");
+        assert!(latest_stable(&[version("0.1.0", true)]).is_none());
+    }
+}
@@ -1,24 +1,66 @@
 #![warn(clippy::pedantic)]
-use std::error::Error;
+use std::{error::Error, process::ExitCode};
 
+use clap::Parser;
+use cli::{Cli, Command};
+use config::Config;
+use preset::all_presets;
+use team_catalog::load_team_catalogs;
 use tui::{
     handler::run,
     tui::{init, init_error_hooks, restore},
 };
 
 mod backend;
+mod cache;
+mod cli;
+mod commands;
+mod config;
 mod content_parser;
+mod crates_io;
 mod dependency_builder;
+mod export;
+mod index;
+mod preset;
+mod profile;
+mod project;
+mod readme;
+mod registry;
 mod scraper;
+mod team_catalog;
 mod tui;
 mod utils;
 mod view;
+mod workspace;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> Result<ExitCode, Box<dyn Error>> {
+    let cli = Cli::parse();
+    let config = Config::load()?;
+
+    let source = cli.source.or(config.source.clone()).unwrap_or_default();
+
+    if let Some(command) = &cli.command {
+        return Ok(match command {
+            Command::Add(args) => match args.export {
+                Some(format) => commands::export(args, format, &config.presets).await,
+                None => commands::add(args, &config.presets),
+            },
+            Command::Presets => commands::presets(&config.presets),
+            Command::List(args) => {
+                commands::list(args, &source, &load_team_catalogs(&config)?).await
+            }
+            Command::Search(args) => {
+                commands::search(args, &source, &load_team_catalogs(&config)?).await
+            }
+        });
+    }
+
+    let team_catalogs = load_team_catalogs(&config)?;
+
     init_error_hooks()?;
     init()?;
-    run().await?;
+    let result = run(source, team_catalogs, all_presets(&config.presets)).await;
     restore()?;
-    Ok(())
+    result.map(|()| ExitCode::SUCCESS)
 }
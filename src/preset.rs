@@ -0,0 +1,251 @@
+/// This module's job is to define bundles of crates which are usually added together, the bundled
+/// ones and the ones the user writes in the config file
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dependency_builder::{CrateToAdd, DependencyKind};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(
+        serialize_with = "serialize_crates",
+        deserialize_with = "deserialize_crates"
+    )]
+    pub crates: Vec<CrateToAdd>,
+}
+
+///A crate of a preset written in the config file, either its name or a table like the entries of
+///Cargo.toml
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum PresetCrate {
+    Name(String),
+    #[serde(rename_all = "kebab-case")]
+    Detailed {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        default_features: Option<bool>,
+        #[serde(default, skip_serializing_if = "is_normal")]
+        kind: DependencyKind,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        registry: Option<String>,
+        ///The key of the crate in Cargo.toml when it is not its name
+        #[serde(skip_serializing_if = "Option::is_none")]
+        rename: Option<String>,
+    },
+}
+
+///Crates without anything but their name are written as just the name
+impl From<&CrateToAdd> for PresetCrate {
+    fn from(value: &CrateToAdd) -> Self {
+        let features = value.features.clone().unwrap_or_default();
+
+        if features.is_empty()
+            && value.default_features
+            && value.version.is_none()
+            && value.kind == DependencyKind::Normal
+            && value.registry.is_none()
+            && value.rename.is_none()
+        {
+            return PresetCrate::Name(value.crate_name.clone());
+        }
+
+        PresetCrate::Detailed {
+            name: value.crate_name.clone(),
+            version: value.version.clone(),
+            features,
+            default_features: (!value.default_features).then_some(false),
+            kind: value.kind,
+            registry: value.registry.clone(),
+            rename: value.rename.clone(),
+        }
+    }
+}
+
+impl From<PresetCrate> for CrateToAdd {
+    fn from(value: PresetCrate) -> Self {
+        match value {
+            PresetCrate::Name(name) => CrateToAdd::new(name),
+            PresetCrate::Detailed {
+                name,
+                version,
+                features,
+                default_features,
+                kind,
+                registry,
+                rename,
+            } => CrateToAdd {
+                features: if features.is_empty() {
+                    None
+                } else {
+                    Some(features)
+                },
+                default_features: default_features.unwrap_or(true),
+                version,
+                kind,
+                registry,
+                rename,
+                ..CrateToAdd::new(name)
+            },
+        }
+    }
+}
+
+//serde passes the field by reference
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_normal(kind: &DependencyKind) -> bool {
+    *kind == DependencyKind::Normal
+}
+
+fn serialize_crates<S>(crates: &[CrateToAdd], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(crates.iter().map(PresetCrate::from))
+}
+
+fn deserialize_crates<'de, D>(deserializer: D) -> Result<Vec<CrateToAdd>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let crates = Vec::<PresetCrate>::deserialize(deserializer)?;
+    Ok(crates.into_iter().map(CrateToAdd::from).collect())
+}
+
+impl Preset {
+    fn bundled(name: &str, description: &str, crates: &[(&str, &[&str])]) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            crates: crates
+                .iter()
+                .map(|(crate_name, features)| CrateToAdd {
+                    features: if features.is_empty() {
+                        None
+                    } else {
+                        Some(features.iter().map(ToString::to_string).collect())
+                    },
+                    ..CrateToAdd::new(*crate_name)
+                })
+                .collect(),
+        }
+    }
+
+    ///e.g. `clap (derive), anyhow, indicatif`
+    pub fn summary(&self) -> String {
+        self.crates
+            .iter()
+            .map(|krate| match &krate.features {
+                Some(features) => format!("{} ({})", krate.crate_name, features.join(", ")),
+                None => krate.crate_name.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+pub fn bundled_presets() -> Vec<Preset> {
+    vec![
+        Preset::bundled(
+            "web-backend",
+            "HTTP services with a database",
+            &[
+                ("axum", &[]),
+                ("tokio", &["full"]),
+                ("serde", &["derive"]),
+                ("serde_json", &[]),
+                ("tracing", &[]),
+                ("tracing-subscriber", &[]),
+                ("sqlx", &["runtime-tokio", "postgres"]),
+            ],
+        ),
+        Preset::bundled(
+            "cli",
+            "Command line tools",
+            &[("clap", &["derive"]), ("anyhow", &[]), ("indicatif", &[])],
+        ),
+    ]
+}
+
+///The presets of the config file come first, one with the name of a bundled preset replaces it
+pub fn all_presets(user_presets: &[Preset]) -> Vec<Preset> {
+    let mut presets = user_presets.to_vec();
+
+    presets.extend(bundled_presets().into_iter().filter(|bundled| {
+        !user_presets
+            .iter()
+            .any(|preset| preset.name == bundled.name)
+    }));
+
+    presets
+}
+
+pub fn find_preset(name: &str, user_presets: &[Preset]) -> Option<Preset> {
+    all_presets(user_presets)
+        .into_iter()
+        .find(|preset| preset.name == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Presets {
+        presets: Vec<Preset>,
+    }
+
+    #[test]
+    fn user_presets_are_read_from_the_config() {
+        let Presets { presets } = toml::from_str(
+            r#"
+            [[presets]]
+            name = "cli"
+            description = "Our command line tools"
+            crates = [
+                "anyhow",
+                { name = "clap", version = "4.5", features = ["derive", "env"] },
+                { name = "assert_cmd", kind = "dev" },
+                { name = "company-logging", registry = "internal", default-features = false },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            presets[0].crates,
+            [
+                CrateToAdd::new("anyhow"),
+                CrateToAdd {
+                    version: Some("4.5".to_string()),
+                    features: Some(vec!["derive".to_string(), "env".to_string()]),
+                    ..CrateToAdd::new("clap")
+                },
+                CrateToAdd {
+                    kind: DependencyKind::Dev,
+                    ..CrateToAdd::new("assert_cmd")
+                },
+                CrateToAdd {
+                    registry: Some("internal".to_string()),
+                    default_features: false,
+                    ..CrateToAdd::new("company-logging")
+                },
+            ]
+        );
+
+        let all = all_presets(&presets);
+        let names: Vec<&str> = all.iter().map(|preset| preset.name.as_str()).collect();
+
+        assert_eq!(names, ["cli", "web-backend"]);
+        assert_eq!(
+            find_preset("cli", &presets).unwrap().description,
+            "Our command line tools"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+/// This module's job is to save the crates the user selected as a named profile and to read them
+/// back later or in another project. Profiles have the same shape as the presets of the config
+/// file, so a teammate can use one as it is or paste it into their presets
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{
+    cache::write_atomically,
+    dependency_builder::{Change, CrateToAdd},
+    preset::Preset,
+};
+
+pub fn profiles_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("get-blessed").join("profiles"))
+}
+
+///The name becomes the file name, so only letters, numbers, `-` and `_` are allowed
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|character| character.is_alphanumeric() || character == '-' || character == '_')
+}
+
+pub fn profile_exists(name: &str) -> bool {
+    profiles_dir().is_some_and(|dir| dir.join(format!("{name}.toml")).is_file())
+}
+
+///Saves the crates being added or updated, removing a crate only makes sense in the project it was
+///removed from. A profile with the same name is only replaced with `replace`. Returns the saved
+///profile and where it was written
+pub fn save_profile(
+    name: &str,
+    crates: &[CrateToAdd],
+    replace: bool,
+) -> io::Result<(Preset, PathBuf)> {
+    let dir = profiles_dir().ok_or_else(|| io::Error::other("There is no config directory"))?;
+    save_profile_to(&dir, name, crates, replace)
+}
+
+fn save_profile_to(
+    dir: &Path,
+    name: &str,
+    crates: &[CrateToAdd],
+    replace: bool,
+) -> io::Result<(Preset, PathBuf)> {
+    if !is_valid_profile_name(name) {
+        return Err(io::Error::other(
+            "A profile name can only have letters, numbers, - and _",
+        ));
+    }
+
+    let path = dir.join(format!("{name}.toml"));
+
+    if path.exists() && !replace {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("There is already a profile called {name}"),
+        ));
+    }
+
+    let project = env::current_dir()
+        .ok()
+        .and_then(|dir| Some(dir.file_name()?.to_string_lossy().to_string()))
+        .unwrap_or_default();
+
+    let profile = Preset {
+        name: name.to_string(),
+        description: format!(
+            "Saved from {project} on {}",
+            Local::now().format("%Y-%m-%d")
+        ),
+        crates: crates
+            .iter()
+            .filter(|krate| krate.change != Change::Remove)
+            .map(|krate| CrateToAdd {
+                change: Change::Add,
+                ..krate.clone()
+            })
+            .collect(),
+    };
+
+    let contents = toml::to_string(&profile).map_err(io::Error::other)?;
+
+    write_atomically(&path, contents.as_bytes())?;
+
+    Ok((profile, path))
+}
+
+///Every profile saved before, sorted by name. The ones which can not be read are left out
+pub fn load_profiles() -> Vec<Preset> {
+    let Some(entries) = profiles_dir().and_then(|dir| fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+
+    let mut profiles: Vec<Preset> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .filter_map(|path| read_profile(&path).ok())
+        .collect();
+
+    profiles.sort_by(|profile, other| profile.name.cmp(&other.name));
+
+    profiles
+}
+
+///`name_or_path` is the name of a saved profile or the path to a profile file, like one shared by
+///a teammate
+pub fn load_profile(name_or_path: &str) -> Result<Preset, Box<dyn Error>> {
+    let path = Path::new(name_or_path);
+
+    if path.is_file() {
+        return read_profile(path);
+    }
+
+    let saved = profiles_dir()
+        .map(|dir| dir.join(format!("{name_or_path}.toml")))
+        .filter(|path| path.is_file())
+        .ok_or_else(|| format!("There is no profile called `{name_or_path}`"))?;
+
+    read_profile(&saved)
+}
+
+fn read_profile(path: &Path) -> Result<Preset, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+
+    toml::from_str(&contents).map_err(|e| format!("Invalid profile {}: {e}", path.display()).into())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{cache::test_dir, dependency_builder::DependencyKind};
+
+    use super::*;
+
+    #[test]
+    fn saved_profile_can_be_loaded_back() {
+        let dir = test_dir("profiles");
+
+        let crates = vec![
+            CrateToAdd::new("anyhow"),
+            CrateToAdd {
+                version: Some("=1.38.0".to_string()),
+                features: Some(vec!["full".to_string()]),
+                default_features: false,
+                ..CrateToAdd::new("tokio")
+            },
+            CrateToAdd {
+                kind: DependencyKind::Dev,
+                change: Change::Update {
+                    removed_features: Vec::new(),
+                },
+                ..CrateToAdd::new("insta")
+            },
+            CrateToAdd {
+                change: Change::Remove,
+                ..CrateToAdd::new("log")
+            },
+            CrateToAdd {
+                rename: Some("json".to_string()),
+                ..CrateToAdd::new("serde_json")
+            },
+        ];
+
+        let (saved, path) = save_profile_to(&dir, "api-service", &crates, false).unwrap();
+        let loaded = read_profile(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+
+        let overwrite = save_profile_to(&dir, "api-service", &crates[..1], false).unwrap_err();
+        let kept = read_profile(&path).unwrap();
+
+        let (replaced, _) = save_profile_to(&dir, "api-service", &crates[..1], true).unwrap();
+
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(overwrite.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(kept, saved);
+        assert_eq!(replaced.crates.len(), 1);
+
+        assert_eq!(loaded, saved);
+        assert_eq!(loaded.name, "api-service");
+
+        let names: Vec<&str> = loaded
+            .crates
+            .iter()
+            .map(|krate| krate.crate_name.as_str())
+            .collect();
+        assert_eq!(names, ["anyhow", "tokio", "insta", "serde_json"]);
+        assert_eq!(loaded.crates[1], crates[1]);
+        assert_eq!(loaded.crates[3], crates[4]);
+        assert!(contents.contains(r#"rename = "json""#));
+        assert_eq!(loaded.crates[2].kind, DependencyKind::Dev);
+
+        assert!(save_profile_to(&dir, "../escape", &crates, false).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+/// This module's job is to find the crates the user's project already depends on, reading its
+/// Cargo.toml and the versions Cargo.lock resolved for them
+use std::{collections::HashMap, fs};
+
+use toml::{Table, Value};
+
+use crate::dependency_builder::{manifest_path, workspace_manifest_path, DependencyKind};
+
+///A crate written in the project's Cargo.toml
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExistingDependency {
+    ///The key of the entry in Cargo.toml, not the crate's name when it was renamed with
+    ///`json = { package = "serde_json" }`
+    pub key: String,
+    ///The requirement written in Cargo.toml, `None` for path or git dependencies without one
+    pub requirement: Option<String>,
+    ///The version Cargo.lock resolved the requirement to
+    pub locked_version: Option<String>,
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub kind: DependencyKind,
+    ///`None` when the crate comes from crates.io
+    pub registry: Option<String>,
+}
+
+impl ExistingDependency {
+    pub fn version(&self) -> Option<&str> {
+        self.locked_version
+            .as_deref()
+            .or(self.requirement.as_deref())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ExistingDependencies {
+    dependencies: HashMap<String, ExistingDependency>,
+}
+
+impl ExistingDependencies {
+    ///Empty when get-blessed is not run inside a cargo project
+    pub fn detect() -> Self {
+        let Ok(manifest) = manifest_path() else {
+            return Self::default();
+        };
+
+        let read_toml = |path| {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|contents| contents.parse::<Table>().ok())
+        };
+
+        let Some(package_manifest) = read_toml(manifest) else {
+            return Self::default();
+        };
+
+        let workspace_manifest = workspace_manifest_path().ok();
+
+        Self::from_manifests(
+            &package_manifest,
+            workspace_manifest.clone().and_then(read_toml).as_ref(),
+            workspace_manifest
+                .and_then(|path| read_toml(path.with_file_name("Cargo.lock")))
+                .as_ref(),
+        )
+    }
+
+    fn from_manifests(
+        manifest: &Table,
+        workspace_manifest: Option<&Table>,
+        lockfile: Option<&Table>,
+    ) -> Self {
+        let workspace_dependencies = workspace_manifest
+            .and_then(|manifest| manifest.get("workspace"))
+            .and_then(|workspace| workspace.get("dependencies"))
+            .and_then(Value::as_table);
+
+        let locked_packages: Vec<(&str, &str)> = lockfile
+            .and_then(|lockfile| lockfile.get("package"))
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|package| {
+                Some((
+                    package.get("name")?.as_str()?,
+                    package.get("version")?.as_str()?,
+                ))
+            })
+            .collect();
+
+        let mut dependencies = HashMap::new();
+
+        for kind in [
+            DependencyKind::Normal,
+            DependencyKind::Dev,
+            DependencyKind::Build,
+        ] {
+            let Some(table) = manifest.get(kind.table()).and_then(Value::as_table) else {
+                continue;
+            };
+
+            for (key, entry) in table {
+                // `serde = { workspace = true }` takes the rest from `[workspace.dependencies]`
+                let inherited = entry
+                    .get("workspace")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false)
+                    .then(|| workspace_dependencies.and_then(|dependencies| dependencies.get(key)))
+                    .flatten();
+
+                // A dependency can be renamed with `package = "real-name"`
+                let crate_name = [Some(entry), inherited]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|entry| entry.get("package").and_then(Value::as_str))
+                    .unwrap_or(key);
+
+                if dependencies.contains_key(crate_name) {
+                    continue;
+                }
+
+                let mut dependency = read_entry(inherited.unwrap_or(entry));
+                dependency.kind = kind;
+                dependency.key.clone_from(key);
+
+                if inherited.is_some() {
+                    for feature in read_entry(entry).features {
+                        if !dependency.features.contains(&feature) {
+                            dependency.features.push(feature);
+                        }
+                    }
+                }
+
+                let versions: Vec<&str> = locked_packages
+                    .iter()
+                    .filter(|(name, _)| *name == crate_name)
+                    .map(|(_, version)| *version)
+                    .collect();
+
+                dependency.locked_version =
+                    locked_version(&versions, dependency.requirement.as_deref());
+
+                dependencies.insert(crate_name.to_string(), dependency);
+            }
+        }
+
+        Self { dependencies }
+    }
+
+    pub fn get(&self, crate_name: &str) -> Option<&ExistingDependency> {
+        self.dependencies.get(crate_name)
+    }
+}
+
+///Reads `crate = "1.0"` or `crate = { version = "1.0", features = [..], default-features = false }`
+fn read_entry(entry: &Value) -> ExistingDependency {
+    if let Some(requirement) = entry.as_str() {
+        return ExistingDependency {
+            requirement: Some(requirement.to_string()),
+            default_features: true,
+            ..ExistingDependency::default()
+        };
+    }
+
+    ExistingDependency {
+        requirement: entry
+            .get("version")
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        features: entry
+            .get("features")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+        default_features: entry
+            .get("default-features")
+            .or(entry.get("default_features"))
+            .and_then(Value::as_bool)
+            .unwrap_or(true),
+        registry: entry
+            .get("registry")
+            .and_then(Value::as_str)
+            .map(ToString::to_string),
+        ..ExistingDependency::default()
+    }
+}
+
+///Cargo.lock can have several versions of a crate, the project uses the one compatible with its
+///requirement, e.g. `1.0.200` for `1` or `0.8.5` for `0.8`
+fn locked_version(versions: &[&str], requirement: Option<&str>) -> Option<String> {
+    let compatible_prefix = requirement.map(|requirement| {
+        let requirement = requirement.trim_start_matches(['^', '=', '~', ' ']);
+        let mut parts = requirement.split('.');
+        match (parts.next(), parts.next()) {
+            (Some("0"), Some(minor)) => format!("0.{minor}."),
+            (major, _) => format!("{}.", major.unwrap_or_default()),
+        }
+    });
+
+    versions
+        .iter()
+        .find(|version| {
+            compatible_prefix
+                .as_ref()
+                .is_some_and(|prefix| format!("{version}.").starts_with(prefix.as_str()))
+        })
+        .or(versions.last())
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dependencies_are_read_with_their_locked_version() {
+        let manifest: Table = r#"
+            [dependencies]
+            serde = { version = "1", features = ["derive"] }
+            rand = "0.8"
+            json = { package = "serde_json", version = "1" }
+            anyhow = { workspace = true, features = ["backtrace"] }
+
+            [dev-dependencies]
+            insta = { version = "1.39", default-features = false }
+        "#
+        .parse()
+        .unwrap();
+
+        let workspace_manifest: Table = r#"
+            [workspace.dependencies]
+            anyhow = { version = "1.0.80", features = ["std"] }
+        "#
+        .parse()
+        .unwrap();
+
+        let lockfile: Table = r#"
+            [[package]]
+            name = "rand"
+            version = "0.7.3"
+
+            [[package]]
+            name = "rand"
+            version = "0.8.5"
+
+            [[package]]
+            name = "serde"
+            version = "1.0.200"
+        "#
+        .parse()
+        .unwrap();
+
+        let existing = ExistingDependencies::from_manifests(
+            &manifest,
+            Some(&workspace_manifest),
+            Some(&lockfile),
+        );
+
+        let serde = existing.get("serde").unwrap();
+        assert_eq!(serde.version(), Some("1.0.200"));
+        assert_eq!(serde.features, ["derive"]);
+
+        assert_eq!(existing.get("rand").unwrap().version(), Some("0.8.5"));
+        assert_eq!(existing.get("serde_json").unwrap().version(), Some("1"));
+        assert_eq!(existing.get("serde_json").unwrap().key, "json");
+        assert!(existing.get("json").is_none());
+
+        let anyhow = existing.get("anyhow").unwrap();
+        assert_eq!(anyhow.requirement.as_deref(), Some("1.0.80"));
+        assert_eq!(anyhow.features, ["std", "backtrace"]);
+
+        let insta = existing.get("insta").unwrap();
+        assert_eq!(insta.kind, DependencyKind::Dev);
+        assert!(!insta.default_features);
+    }
+}
@@ -0,0 +1,455 @@
+/// This module's job is to get a crate's README from the package published on crates.io and to
+/// turn its markdown into styled lines for the terminal
+use std::{
+    collections::HashMap,
+    error::Error,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Style, Stylize},
+    text::{Line, Span},
+};
+use tar::Archive;
+
+///Packages bigger than this are not downloaded only to show their README, most are well under
+///a megabyte
+const MAX_PACKAGE_SIZE: usize = 10 * 1024 * 1024;
+
+///How much of the package is unpacked to find its README, a small package can still unpack to
+///far more than it weighs
+const MAX_UNPACKED_SIZE: u64 = 10 * 1024 * 1024;
+
+///Where cargo looks for the README when `package.readme` does not say, any other file starting
+///with `readme` is tried after them
+const DEFAULT_READMES: [&str; 3] = ["README.md", "README.txt", "README"];
+
+///crates.io only serves the README rendered to html, the markdown is in the `.crate` file.
+///`client` is the one of `CratesIoClient`, which introduces itself to crates.io
+pub async fn fetch_readme(
+    client: &reqwest::Client,
+    crate_name: &str,
+    version: &str,
+) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let url = format!("https://static.crates.io/crates/{crate_name}/{crate_name}-{version}.crate");
+
+    let too_big = || format!("the package of {crate_name} is too big to read its README");
+
+    let mut response = client.get(url).send().await?.error_for_status()?;
+
+    if response
+        .content_length()
+        .is_some_and(|length| length > MAX_PACKAGE_SIZE as u64)
+    {
+        return Err(too_big().into());
+    }
+
+    // The length is not always sent, so the download is stopped once it gets too big
+    let mut package = Vec::new();
+
+    while let Some(chunk) = response.chunk().await? {
+        package.extend_from_slice(&chunk);
+
+        if package.len() > MAX_PACKAGE_SIZE {
+            return Err(too_big().into());
+        }
+    }
+
+    read_readme(&package)
+}
+
+///The README is the file `package.readme` of the packaged Cargo.toml points to, the one cargo
+///would pick when it does not say
+fn read_readme(package: &[u8]) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let mut archive = Archive::new(GzDecoder::new(package));
+    let mut files: HashMap<PathBuf, String> = HashMap::new();
+    let mut unpacked_size = 0;
+    let mut is_too_big = false;
+
+    for entry in archive.entries()? {
+        let entry = entry?;
+
+        // Every file is inside a `crate-version/` directory
+        let path: PathBuf = entry.path()?.components().skip(1).collect();
+
+        let is_wanted = path == Path::new("Cargo.toml")
+            || path.extension().is_some_and(|extension| {
+                extension.eq_ignore_ascii_case("md") || extension.eq_ignore_ascii_case("markdown")
+            })
+            || path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().to_lowercase().starts_with("readme"));
+
+        if !is_wanted {
+            continue;
+        }
+
+        // One byte more than what is left tells a file which is too big apart from one which fits
+        let left = MAX_UNPACKED_SIZE - unpacked_size;
+        let mut contents = Vec::new();
+        entry.take(left + 1).read_to_end(&mut contents)?;
+
+        if contents.len() as u64 > left {
+            is_too_big = true;
+            break;
+        }
+
+        unpacked_size += contents.len() as u64;
+
+        if let Ok(contents) = String::from_utf8(contents) {
+            files.insert(path, contents);
+        }
+    }
+
+    let readme_path = files
+        .get(Path::new("Cargo.toml"))
+        .and_then(|manifest| manifest.parse::<toml::Table>().ok())
+        .and_then(|manifest| {
+            manifest
+                .get("package")?
+                .get("readme")?
+                .as_str()
+                .map(PathBuf::from)
+        });
+
+    if let Some(readme) = readme_path.and_then(|path| files.remove(&path)) {
+        return Ok(readme);
+    }
+
+    let mut other_readmes: Vec<&PathBuf> = files
+        .keys()
+        .filter(|path| {
+            path.parent() == Some(Path::new(""))
+                && path.to_string_lossy().to_lowercase().starts_with("readme")
+        })
+        .collect();
+    other_readmes.sort();
+
+    let fallback = DEFAULT_READMES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| files.contains_key(path))
+        .or_else(|| other_readmes.first().copied().cloned());
+
+    fallback
+        .and_then(|path| files.remove(&path))
+        .ok_or_else(|| {
+            if is_too_big {
+                "The files of this version of the crate are too big to read its README".into()
+            } else {
+                "This version of the crate was published without a README".into()
+            }
+        })
+}
+
+///Headings, code blocks, lists, quotes and tables keep their shape, links and images show their
+///text and html is left out
+pub fn markdown_lines(markdown: &str) -> Vec<Line<'static>> {
+    let mut renderer = MarkdownRenderer::default();
+
+    for event in Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS,
+    ) {
+        renderer.push(event);
+    }
+
+    renderer.flush();
+
+    // No blank line at the end
+    while renderer.lines.last().is_some_and(|line| line.width() == 0) {
+        renderer.lines.pop();
+    }
+
+    renderer.lines
+}
+
+#[derive(Default)]
+struct MarkdownRenderer {
+    lines: Vec<Line<'static>>,
+    ///The spans of the line being written
+    current: Vec<Span<'static>>,
+    ///Emphasis, links and headings nest, the innermost style is the last one
+    styles: Vec<Style>,
+    ///One entry per nested list, the next number for ordered ones
+    lists: Vec<Option<u64>>,
+    quote_depth: usize,
+    is_in_code_block: bool,
+    is_first_table_cell: bool,
+}
+
+impl MarkdownRenderer {
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, style: Style) {
+        self.styles.push(self.style().patch(style));
+    }
+
+    fn flush(&mut self) {
+        if self.current.is_empty() {
+            return;
+        }
+
+        let mut spans = Vec::new();
+
+        if self.quote_depth > 0 {
+            spans.push("│ ".repeat(self.quote_depth).dim());
+        }
+
+        spans.append(&mut self.current);
+        self.lines.push(Line::from(spans));
+    }
+
+    ///Separates blocks, never more than one blank line in a row
+    fn blank_line(&mut self) {
+        self.flush();
+        if self.lines.last().is_some_and(|line| line.width() > 0) {
+            self.lines.push(Line::default());
+        }
+    }
+
+    fn push(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.is_in_code_block => {
+                for line in text.lines() {
+                    self.current.push("  ".into());
+                    self.current.push(line.to_string().green());
+                    self.flush();
+                }
+            }
+            Event::Text(text) => self
+                .current
+                .push(Span::styled(text.to_string(), self.style())),
+            Event::Code(code) => self.current.push(code.to_string().yellow()),
+            Event::SoftBreak => self.current.push(" ".into()),
+            Event::HardBreak => self.flush(),
+            Event::Rule => {
+                self.flush();
+                self.lines.push(Line::from("─".repeat(40).dim()));
+                self.blank_line();
+            }
+            Event::TaskListMarker(checked) => {
+                self.current
+                    .push(if checked { "[x] " } else { "[ ] " }.into());
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Heading { level, .. } => {
+                self.flush();
+                let style = match level {
+                    HeadingLevel::H1 => Style::new().bold().yellow().underlined(),
+                    HeadingLevel::H2 => Style::new().bold().yellow(),
+                    _ => Style::new().bold().blue(),
+                };
+                self.push_style(style);
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush();
+                self.is_in_code_block = true;
+                if let CodeBlockKind::Fenced(language) = kind {
+                    if !language.is_empty() {
+                        self.lines.push(Line::from(format!("  {language}").dim()));
+                    }
+                }
+            }
+            Tag::List(start) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush();
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{indent}{}. ", *number - 1)
+                    }
+                    _ => format!("{indent}• "),
+                };
+                self.current.push(bullet.blue());
+            }
+            Tag::BlockQuote => {
+                self.flush();
+                self.quote_depth += 1;
+            }
+            Tag::Emphasis => self.push_style(Style::new().italic()),
+            Tag::Strong => self.push_style(Style::new().bold()),
+            Tag::Strikethrough => self.push_style(Style::new().crossed_out()),
+            Tag::Link { .. } => self.push_style(Style::new().blue().underlined()),
+            Tag::Image { .. } => {
+                self.current.push("[image: ".dim());
+                self.push_style(Style::new().dim());
+            }
+            Tag::TableRow | Tag::TableHead => {
+                self.flush();
+                self.is_first_table_cell = true;
+            }
+            Tag::TableCell => {
+                if !self.is_first_table_cell {
+                    self.current.push(" │ ".dim());
+                }
+                self.is_first_table_cell = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Heading(_) => {
+                self.styles.pop();
+                self.blank_line();
+            }
+            TagEnd::Paragraph if !self.lists.is_empty() => self.flush(),
+            TagEnd::Paragraph | TagEnd::Table => self.blank_line(),
+            TagEnd::CodeBlock => {
+                self.is_in_code_block = false;
+                self.blank_line();
+            }
+            TagEnd::List(_) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            TagEnd::Item | TagEnd::TableRow | TagEnd::TableHead => self.flush(),
+            TagEnd::BlockQuote => {
+                self.flush();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                self.blank_line();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Link => {
+                self.styles.pop();
+            }
+            TagEnd::Image => {
+                self.styles.pop();
+                self.current.push("]".dim());
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn markdown_keeps_its_shape() {
+        let markdown = "# Serde
+
+Serde is a **framework** for *serializing*.
+
+- one
+- two
+  1. nested
+
+```rust
+let x = 1;
+```
+
+> quoted
+
+| a | b |
+|---|---|
+| 1 | 2 |
+";
+
+        assert_eq!(
+            plain(&markdown_lines(markdown)),
+            [
+                "Serde",
+                "",
+                "Serde is a framework for serializing.",
+                "",
+                "• one",
+                "• two",
+                "  1. nested",
+                "",
+                "  rust",
+                "  let x = 1;",
+                "",
+                "│ quoted",
+                "",
+                "a │ b",
+                "1 │ 2",
+            ]
+        );
+    }
+
+    fn package(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(contents.len() as u64);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *contents).unwrap();
+        }
+
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn the_readme_comes_from_the_package() {
+        let package = package(&[
+            (
+                "demo-0.1.0/Cargo.toml",
+                b"[package]\nname = \"demo\"\nreadme = \"docs/intro.md\"\n",
+            ),
+            ("demo-0.1.0/README.md", b"the root one"),
+            ("demo-0.1.0/docs/intro.md", b"# Demo"),
+        ]);
+
+        assert_eq!(read_readme(&package).unwrap(), "# Demo");
+    }
+
+    #[test]
+    fn readmes_cargo_would_pick_are_found() {
+        let lowercase = package(&[
+            ("demo-0.1.0/Cargo.toml", b"[package]\nname = \"demo\"\n"),
+            ("demo-0.1.0/readme.md", b"lowercase"),
+        ]);
+        let without_extension = package(&[
+            ("demo-0.1.0/Cargo.toml", b"[package]\nname = \"demo\"\n"),
+            ("demo-0.1.0/README", b"plain"),
+            ("demo-0.1.0/docs/README.md", b"not the root one"),
+        ]);
+
+        assert_eq!(read_readme(&lowercase).unwrap(), "lowercase");
+        assert_eq!(read_readme(&without_extension).unwrap(), "plain");
+    }
+
+    #[test]
+    fn readmes_are_not_unpacked_past_the_limit() {
+        let huge = vec![b'a'; usize::try_from(MAX_UNPACKED_SIZE).unwrap() + 1];
+        let package = package(&[
+            ("demo-0.1.0/Cargo.toml", b"[package]\nname = \"demo\"\n"),
+            ("demo-0.1.0/README.md", &huge),
+        ]);
+
+        assert!(read_readme(&package)
+            .unwrap_err()
+            .to_string()
+            .contains("too big"));
+    }
+}
@@ -0,0 +1,168 @@
+/// This module's job is to read the registries cargo knows about besides crates.io, from the
+/// `.cargo/config.toml` files cargo reads and the tokens of `credentials.toml`
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::index::{cargo_home, IndexClient};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Registry {
+    ///As written in the config, like `sparse+https://crates.my-company.com/index/`
+    pub index: String,
+    ///Private registries ask for it to read their index
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Registries {
+    registries: BTreeMap<String, Registry>,
+}
+
+impl Registries {
+    ///Cargo reads `.cargo/config.toml` in the current directory and in every parent, then the one
+    ///in cargo's home. The closer to the current directory, the more important the config is
+    pub fn detect() -> Self {
+        let mut cargo_dirs: Vec<PathBuf> = env::current_dir()
+            .map(|dir| dir.ancestors().map(|dir| dir.join(".cargo")).collect())
+            .unwrap_or_default();
+
+        if let Some(cargo_home) = cargo_home().filter(|home| !cargo_dirs.contains(home)) {
+            cargo_dirs.push(cargo_home);
+        }
+
+        let configs: Vec<Table> = cargo_dirs
+            .iter()
+            .filter_map(|dir| read_config(dir, "config"))
+            .collect();
+
+        let credentials = cargo_home().and_then(|home| read_config(&home, "credentials"));
+
+        let mut registries = Self::from_configs(&configs, credentials.as_ref());
+        registries.read_environment();
+        registries
+    }
+
+    ///`configs` go from the most to the least important
+    fn from_configs(configs: &[Table], credentials: Option<&Table>) -> Self {
+        let mut registries: BTreeMap<String, Registry> = BTreeMap::new();
+
+        for (name, index, token) in configs.iter().rev().flat_map(registry_tables) {
+            let registry = registries.entry(name).or_default();
+
+            if let Some(index) = index {
+                registry.index = index;
+            }
+            if token.is_some() {
+                registry.token = token;
+            }
+        }
+
+        // `cargo login` writes the tokens to credentials.toml, they win over the configs
+        for (name, _, token) in credentials.into_iter().flat_map(registry_tables) {
+            if let Some(registry) = registries.get_mut(&name).filter(|_| token.is_some()) {
+                registry.token = token;
+            }
+        }
+
+        registries.retain(|_, registry| !registry.index.is_empty());
+
+        Self { registries }
+    }
+
+    ///`CARGO_REGISTRIES_<NAME>_INDEX` and `CARGO_REGISTRIES_<NAME>_TOKEN` win over the configs
+    fn read_environment(&mut self) {
+        for (name, registry) in &mut self.registries {
+            let variable = format!("CARGO_REGISTRIES_{}", name.to_uppercase().replace('-', "_"));
+
+            if let Ok(index) = env::var(format!("{variable}_INDEX")) {
+                registry.index = index;
+            }
+            if let Ok(token) = env::var(format!("{variable}_TOKEN")) {
+                registry.token = Some(token);
+            }
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Registry> {
+        self.registries.get(name)
+    }
+
+    pub fn index_client(&self, name: &str) -> Option<IndexClient> {
+        self.get(name)
+            .map(|registry| IndexClient::new(&registry.index).token(registry.token.clone()))
+    }
+}
+
+///`config.toml` is preferred, cargo still reads the file without extension
+fn read_config(dir: &Path, name: &str) -> Option<Table> {
+    [format!("{name}.toml"), name.to_string()]
+        .iter()
+        .find_map(|file| fs::read_to_string(dir.join(file)).ok())
+        .and_then(|contents| contents.parse().ok())
+}
+
+///The `[registries.<name>]` tables of a config with their index and token
+fn registry_tables(config: &Table) -> Vec<(String, Option<String>, Option<String>)> {
+    config
+        .get("registries")
+        .and_then(Value::as_table)
+        .into_iter()
+        .flatten()
+        .map(|(name, registry)| {
+            let read = |key: &str| registry.get(key).and_then(Value::as_str).map(String::from);
+            (name.clone(), read("index"), read("token"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn closest_config_wins_and_tokens_come_from_credentials() {
+        let project: Table = r#"
+            [registries.internal]
+            index = "sparse+https://crates.my-company.com/index/"
+        "#
+        .parse()
+        .unwrap();
+
+        let home: Table = r#"
+            [registries.internal]
+            index = "sparse+https://old.my-company.com/index/"
+
+            [registries.mirror]
+            index = "sparse+https://mirror.my-company.com/"
+        "#
+        .parse()
+        .unwrap();
+
+        let credentials: Table = r#"
+            [registries.internal]
+            token = "secret"
+
+            [registries.unknown]
+            token = "no index for it"
+        "#
+        .parse()
+        .unwrap();
+
+        let registries = Registries::from_configs(&[project, home], Some(&credentials));
+
+        assert_eq!(
+            registries.get("internal"),
+            Some(&Registry {
+                index: "sparse+https://crates.my-company.com/index/".to_string(),
+                token: Some("secret".to_string()),
+            })
+        );
+        assert_eq!(registries.get("mirror").unwrap().token, None);
+        assert!(registries.get("unknown").is_none());
+    }
+}
@@ -1,35 +1,145 @@
-use serde::{Deserialize};
+use std::{
+    convert::Infallible,
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    str::FromStr,
+};
 
-#[derive(Deserialize, Debug, Default, Clone)]
+use serde::{Deserialize, Serialize};
+
+pub const BLESSED_RS_URL: &str =
+    "https://raw.githubusercontent.com/nicoburns/blessed-rs/main/data/crates.json";
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Group {
     pub name: String,
     pub subgroups: Option<Vec<Group>>,
     pub purposes: Option<Vec<Purpose>>,
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Purpose {
     pub name: String,
     pub recommendations: Vec<Recommendation>,
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Recommendation {
     pub name: String,
     pub notes: Option<String>,
+    ///Only team catalogs rate crates, a crate without it comes from blessed.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_status: Option<TeamStatus>,
+    ///What the team has to say about a crate which is also recommended by blessed.rs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub team_notes: Option<String>,
+    ///The registry of `.cargo/config.toml` publishing the crate, `None` for crates.io
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+}
+
+/// How a team catalog rates a crate
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamStatus {
+    #[default]
+    Approved,
+    Discouraged,
+    ///The crate is removed from the list entirely
+    Hidden,
 }
 
-#[derive(Deserialize, Debug, Default, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CratesData {
     pub crate_groups: Vec<Group>,
 }
 
-pub async fn scrape_site() -> Result<CratesData, reqwest::Error> {
-    let response = reqwest::get(
-        "https://raw.githubusercontent.com/nicoburns/blessed-rs/main/data/crates.json",
-    )
-    .await?
-    .json::<CratesData>()
-    .await?;
+/// Where the crates list is read from, it must follow the same schema as blessed.rs' crates.json
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String")]
+pub enum CatalogSource {
+    Url(String),
+    File(PathBuf),
+    ///The crates list is piped into the program
+    Stdin,
+}
+
+impl Default for CatalogSource {
+    fn default() -> Self {
+        Self::Url(BLESSED_RS_URL.to_string())
+    }
+}
+
+impl From<String> for CatalogSource {
+    fn from(value: String) -> Self {
+        if value == "-" {
+            Self::Stdin
+        } else if value.starts_with("http://") || value.starts_with("https://") {
+            Self::Url(value)
+        } else {
+            Self::File(PathBuf::from(value))
+        }
+    }
+}
+
+impl FromStr for CatalogSource {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.to_string().into())
+    }
+}
+
+impl Display for CatalogSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Url(url) => write!(f, "{url}"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+pub async fn scrape_site(url: &str) -> Result<CratesData, reqwest::Error> {
+    let response = reqwest::get(url).await?.json::<CratesData>().await?;
     Ok(response)
 }
+
+pub async fn load_crates_data(
+    source: &CatalogSource,
+) -> Result<CratesData, Box<dyn Error + Send + Sync>> {
+    let crates_data = match source {
+        CatalogSource::Url(url) => scrape_site(url).await?,
+        CatalogSource::File(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+        CatalogSource::Stdin => {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            serde_json::from_str(&contents)?
+        }
+    };
+
+    Ok(crates_data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn catalog_source_is_parsed_from_its_argument() {
+        assert_eq!(CatalogSource::from_str("-").unwrap(), CatalogSource::Stdin);
+
+        assert_eq!(
+            CatalogSource::from_str("https://example.com/crates.json").unwrap(),
+            CatalogSource::Url("https://example.com/crates.json".to_string())
+        );
+
+        assert_eq!(
+            CatalogSource::from_str("./crates.json").unwrap(),
+            CatalogSource::File(PathBuf::from("./crates.json"))
+        );
+    }
+}
@@ -0,0 +1,342 @@
+/// This module's job is to merge the crates lists curated by the user's team with the one from
+/// blessed.rs
+use std::{
+    collections::BTreeSet,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    config::Config,
+    scraper::{CratesData, Group, Purpose, Recommendation, TeamStatus},
+};
+
+///Every `.json` or `.toml` file in these directories is a team catalog
+fn team_catalog_directories() -> Vec<PathBuf> {
+    let mut directories = vec![PathBuf::from(".get-blessed")];
+
+    if let Some(config_dir) = dirs::config_dir() {
+        directories.push(config_dir.join("get-blessed").join("catalogs"));
+    }
+
+    directories
+}
+
+pub fn team_catalog_files(config: &Config) -> Vec<PathBuf> {
+    let mut files = config.team_catalogs.clone();
+
+    for directory in team_catalog_directories() {
+        let Ok(entries) = fs::read_dir(directory) else {
+            continue;
+        };
+
+        let mut catalogs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json" || extension == "toml")
+            })
+            .collect();
+
+        catalogs.sort();
+
+        files.extend(catalogs);
+    }
+
+    files
+}
+
+///Team catalogs follow the same schema as blessed.rs' crates.json, either as json or toml
+pub fn load_team_catalog(path: &Path) -> Result<CratesData, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Could not read team catalog {}: {e}", path.display()))?;
+
+    let catalog = if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        toml::from_str(&contents).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&contents).map_err(|e| e.to_string())
+    };
+
+    catalog.map_err(|e| format!("Invalid team catalog {}: {e}", path.display()).into())
+}
+
+pub fn load_team_catalogs(config: &Config) -> Result<Vec<CratesData>, Box<dyn Error>> {
+    team_catalog_files(config)
+        .iter()
+        .map(|path| load_team_catalog(path))
+        .collect()
+}
+
+///Crates rated by a team which blessed.rs also recommends keep their place in the list and only
+///get the team's status and notes, the rest are added to the group and purpose where the team put
+///them. Hidden crates are removed from the list
+pub fn merge_team_catalogs(mut upstream: CratesData, team_catalogs: &[CratesData]) -> CratesData {
+    for team_catalog in team_catalogs {
+        for group in &team_catalog.crate_groups {
+            merge_group(&mut upstream, group, &mut Vec::new());
+        }
+    }
+
+    let hidden_crates: BTreeSet<String> = recommendations(&upstream.crate_groups)
+        .into_iter()
+        .filter(|recommendation| recommendation.team_status == Some(TeamStatus::Hidden))
+        .map(|recommendation| recommendation.name.clone())
+        .collect();
+
+    for purpose in purposes_mut(&mut upstream.crate_groups) {
+        purpose
+            .recommendations
+            .retain(|recommendation| !hidden_crates.contains(&recommendation.name));
+    }
+
+    upstream
+}
+
+fn merge_group<'a>(upstream: &mut CratesData, team_group: &'a Group, path: &mut Vec<&'a str>) {
+    path.push(&team_group.name);
+
+    for team_purpose in team_group.purposes.iter().flatten() {
+        for team_recommendation in &team_purpose.recommendations {
+            let team_status = team_recommendation.team_status.unwrap_or_default();
+
+            let mut is_upstream = false;
+
+            for recommendation in purposes_mut(&mut upstream.crate_groups)
+                .into_iter()
+                .flat_map(|purpose| purpose.recommendations.iter_mut())
+                .filter(|recommendation| recommendation.name == team_recommendation.name)
+            {
+                is_upstream = true;
+                recommendation.team_status = Some(team_status);
+                if team_recommendation.notes.is_some() {
+                    recommendation
+                        .team_notes
+                        .clone_from(&team_recommendation.notes);
+                }
+                if team_recommendation.registry.is_some() {
+                    recommendation
+                        .registry
+                        .clone_from(&team_recommendation.registry);
+                }
+            }
+
+            if !is_upstream && team_status != TeamStatus::Hidden {
+                find_or_insert_purpose(&mut upstream.crate_groups, path, &team_purpose.name)
+                    .recommendations
+                    .push(Recommendation {
+                        team_status: Some(team_status),
+                        ..team_recommendation.clone()
+                    });
+            }
+        }
+    }
+
+    for subgroup in team_group.subgroups.iter().flatten() {
+        merge_group(upstream, subgroup, path);
+    }
+
+    path.pop();
+}
+
+fn same_name(name: &str, other: &str) -> bool {
+    name.trim().eq_ignore_ascii_case(other.trim())
+}
+
+fn find_or_insert_purpose<'a>(
+    groups: &'a mut Vec<Group>,
+    path: &[&str],
+    purpose_name: &str,
+) -> &'a mut Purpose {
+    let (group_name, subgroups_path) = path
+        .split_first()
+        .expect("a purpose always belongs to a group");
+
+    let index = groups
+        .iter()
+        .position(|group| same_name(&group.name, group_name))
+        .unwrap_or_else(|| {
+            groups.push(Group {
+                name: (*group_name).to_string(),
+                ..Group::default()
+            });
+            groups.len() - 1
+        });
+
+    let group = &mut groups[index];
+
+    if !subgroups_path.is_empty() {
+        return find_or_insert_purpose(
+            group.subgroups.get_or_insert_with(Vec::new),
+            subgroups_path,
+            purpose_name,
+        );
+    }
+
+    let purposes = group.purposes.get_or_insert_with(Vec::new);
+
+    let index = purposes
+        .iter()
+        .position(|purpose| same_name(&purpose.name, purpose_name))
+        .unwrap_or_else(|| {
+            purposes.push(Purpose {
+                name: purpose_name.to_string(),
+                ..Purpose::default()
+            });
+            purposes.len() - 1
+        });
+
+    &mut purposes[index]
+}
+
+fn purposes_mut(groups: &mut [Group]) -> Vec<&mut Purpose> {
+    let mut purposes = Vec::new();
+
+    for group in groups {
+        if let Some(group_purposes) = group.purposes.as_mut() {
+            purposes.extend(group_purposes.iter_mut());
+        }
+        if let Some(subgroups) = group.subgroups.as_mut() {
+            purposes.extend(purposes_mut(subgroups));
+        }
+    }
+
+    purposes
+}
+
+fn recommendations(groups: &[Group]) -> Vec<&Recommendation> {
+    let mut recommendations = Vec::new();
+
+    for group in groups {
+        for purpose in group.purposes.iter().flatten() {
+            recommendations.extend(purpose.recommendations.iter());
+        }
+        if let Some(subgroups) = &group.subgroups {
+            recommendations.extend(self::recommendations(subgroups));
+        }
+    }
+
+    recommendations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn upstream() -> CratesData {
+        serde_json::from_str(
+            &fs::read_to_string(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/crates.json"
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+    }
+
+    fn team_catalog() -> CratesData {
+        toml::from_str(
+            r#"
+            [[crate_groups]]
+            name = "Common"
+
+            [[crate_groups.subgroups]]
+            name = "Logging"
+
+            [[crate_groups.subgroups.purposes]]
+            name = "Text-based logging"
+
+            [[crate_groups.subgroups.purposes.recommendations]]
+            name = "log"
+            team_status = "discouraged"
+            notes = "Use tracing in new services"
+
+            [[crate_groups.subgroups.purposes.recommendations]]
+            name = "tracing-subscriber"
+            notes = "Our logging setup"
+
+            [[crate_groups]]
+            name = "Internal"
+
+            [[crate_groups.purposes]]
+            name = "Telemetry"
+
+            [[crate_groups.purposes.recommendations]]
+            name = "ureq"
+            team_status = "hidden"
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn find<'a>(crates_data: &'a CratesData, name: &str) -> Vec<&'a Recommendation> {
+        recommendations(&crates_data.crate_groups)
+            .into_iter()
+            .filter(|recommendation| recommendation.name == name)
+            .collect()
+    }
+
+    #[test]
+    fn upstream_crates_get_the_team_status_and_notes() {
+        let merged = merge_team_catalogs(upstream(), &[team_catalog()]);
+
+        let log = find(&merged, "log");
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].team_status, Some(TeamStatus::Discouraged));
+        assert_eq!(
+            log[0].team_notes.as_deref(),
+            Some("Use tracing in new services")
+        );
+        assert!(log[0].notes.is_some());
+
+        assert_eq!(find(&merged, "tracing")[0].team_status, None);
+    }
+
+    #[test]
+    fn team_crates_are_added_where_the_team_put_them() {
+        let merged = merge_team_catalogs(upstream(), &[team_catalog()]);
+
+        let common = merged
+            .crate_groups
+            .iter()
+            .find(|group| group.name == "Common")
+            .unwrap();
+
+        let logging = common
+            .subgroups
+            .iter()
+            .flatten()
+            .find(|subgroup| subgroup.name == "Logging")
+            .unwrap();
+
+        let recommendations: Vec<(&str, Option<TeamStatus>)> = logging.purposes.as_ref().unwrap()
+            [0]
+        .recommendations
+        .iter()
+        .map(|recommendation| (recommendation.name.as_str(), recommendation.team_status))
+        .collect();
+
+        assert_eq!(
+            recommendations,
+            vec![
+                ("tracing", None),
+                ("log", Some(TeamStatus::Discouraged)),
+                ("tracing-subscriber", Some(TeamStatus::Approved))
+            ]
+        );
+    }
+
+    #[test]
+    fn hidden_crates_are_removed() {
+        let merged = merge_team_catalogs(upstream(), &[team_catalog()]);
+
+        assert!(find(&merged, "ureq").is_empty());
+        assert!(!merged
+            .crate_groups
+            .iter()
+            .any(|group| group.name == "Internal" && group.purposes.is_some()));
+    }
+}
@@ -1,23 +1,61 @@
-use core::panic;
-use std::env;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{error::Error, time::Duration};
 
+use crates_io_api::CrateResponse;
 use crossterm::event::{self, poll, Event, KeyCode, KeyEventKind};
 use ratatui::{backend::CrosstermBackend, Terminal};
 use tokio::sync::mpsc::{self, UnboundedSender};
 
+use crate::cache::{load_catalog, save_catalog, CatalogStatus};
 use crate::content_parser::jsoncontentparser::JsonContentParser;
-use crate::utils::{load_features, select_crate_if_features_are_selected};
-use crate::view::widgets::{CategoriesWidget, CrateItemList, FeatureItemList};
-use crate::{dependency_builder::DependenciesBuilder, view::app::App};
+use crate::crates_io::CratesIoClient;
+use crate::export::find_latest_versions;
+use crate::index::{latest_stable, IndexClient, IndexVersion};
+use crate::preset::Preset;
+use crate::profile::load_profiles;
+use crate::readme::fetch_readme;
+use crate::registry::Registries;
+use crate::scraper::{load_crates_data, scrape_site, CatalogSource, CratesData};
+use crate::team_catalog::merge_team_catalogs;
+use crate::utils::{enabled_by_default, select_crate_if_customized};
+use crate::view::widgets::{CrateDetails, FeatureItemList, VersionItemList};
+use crate::workspace::Workspace;
+use crate::{
+    dependency_builder::{AddReport, DependenciesBuilder, DependencyPreview},
+    view::app::App,
+};
+
+///How far `<PageDown>` and `<PageUp>` move the README
+const README_PAGE_LINES: u16 = 20;
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Action {
+    ReloadCatalog(CratesData),
+    CatalogUpToDate,
+    CatalogOffline,
     FetchFeatures,
-    UpdateFeatures(CategoriesWidget, Option<Vec<FeatureItemList>>, usize),
+    UpdateFeatures(String, Option<Vec<FeatureItemList>>),
+    UpdateVersions(String, Option<Vec<VersionItemList>>),
+    UpdateDetails(String, CrateDetails),
+    UpdateReadme(String, Result<String, String>),
     Tick,
     ToggleShowFeatures,
+    ToggleDefaultFeatures,
+    ToggleShowVersions,
+    CycleVersionRequirement,
+    FocusCratesToAdd,
+    ToggleShowPackages,
+    ToggleShowPresets,
+    SaveProfile,
+    ToggleExport,
+    UpdateExportVersions(BTreeMap<String, String>, Vec<String>),
+    ToggleWorkspaceDependencies,
+    FocusCratesList,
+    CycleDependencyKind,
+    PreviewDependencies,
+    UpdatePreview(Vec<DependencyPreview>),
     ShowLoadingAddingDeps,
     AddingDeps,
     ScrollUp,
@@ -26,57 +64,282 @@ pub enum Action {
     ScrollPreviousCategory,
     ToggleAll,
     ToggleOne,
+    ToggleSection,
     CheckDocs,
     CheckCratesIo,
-    ShowAddingDependenciesOperation,
+    ToggleShowReadme,
+    PageUp,
+    PageDown,
+    ShowAddResults(AddReport),
+    RestoreManifest,
+    StartSearch,
+    TypeSearch(char),
+    DeleteSearchCharacter,
+    ConfirmSearch,
+    CloseSearch,
+    TypeProfileName(char),
+    DeleteProfileNameCharacter,
+    ConfirmProfileName,
+    CancelProfileName,
     Quit,
 }
 
 #[allow(clippy::too_many_lines)]
 pub fn update(app: &mut App, action: Action) {
+    //Once cargo add finished the only thing left is reading the results
+    if app.is_showing_add_results() {
+        match action {
+            Action::ScrollDown => app.scroll_down_add_results(),
+            Action::ScrollUp => app.scroll_up_add_results(),
+            Action::RestoreManifest => app.restore_manifest(),
+            Action::PreviewDependencies | Action::Quit => app.exit(),
+            Action::Tick => app.on_tick(),
+            _ => {}
+        }
+        return;
+    }
+
+    //While the README is open the keys only move through it, what is fetched in the background
+    //is still loaded
+    if app.is_showing_readme() && is_from_user(&action) {
+        match action {
+            Action::ScrollDown => app.scroll_down_readme(1),
+            Action::ScrollUp => app.scroll_up_readme(1),
+            Action::PageDown => app.scroll_down_readme(README_PAGE_LINES),
+            Action::PageUp => app.scroll_up_readme(README_PAGE_LINES),
+            Action::ToggleShowReadme | Action::Quit => app.close_readme(),
+            _ => {}
+        }
+        return;
+    }
+
+    //Same for the export, its keys are the ones of the main screen doing something else
+    if app.is_showing_export() && is_from_user(&action) {
+        match action {
+            Action::ScrollDown => app.scroll_down_export(),
+            Action::ScrollUp => app.scroll_up_export(),
+            Action::ScrollNextCategory => app.cycle_export_format(),
+            Action::PreviewDependencies => app.copy_export(),
+            Action::ToggleShowPackages => app.write_export(),
+            Action::ToggleExport | Action::Quit => app.close_export(),
+            _ => {}
+        }
+        return;
+    }
+
     match action {
         Action::ToggleShowFeatures => {
+            if app.is_showing_popup() && !app.is_showing_features {
+                return;
+            }
             app.toggle_show_features();
             //After user closes the popup where they can se the features we check if we can add
             //the crate if the user selected at least 1 feature
             //THe way to do this must be improved since it is really ugly
             if !app.is_showing_features {
-                select_crate_if_features_are_selected(app);
+                select_crate_if_customized(app);
                 app.push_or_remove_selected_crates();
             }
         }
-        Action::ShowAddingDependenciesOperation => {
-            let tx = app.action_tx.clone();
-            app.set_adding_deps_operation_message("Dependencies added successfully ✓");
+        Action::ToggleShowVersions => {
+            if app.is_showing_popup() && !app.is_showing_versions {
+                return;
+            }
+            app.toggle_show_versions();
+            if !app.is_showing_versions {
+                select_crate_if_customized(app);
+                app.push_or_remove_selected_crates();
+            }
+        }
+        Action::ToggleShowPackages => {
+            if app.is_showing_packages || !app.is_showing_popup() {
+                app.toggle_show_packages();
+            }
+        }
+        Action::SaveProfile => {
+            if !app.is_showing_popup() && !app.crates_to_add.widget.crates.is_empty() {
+                app.start_saving_profile();
+            }
+        }
+        Action::ToggleExport => {
+            if !app.is_showing_popup() && !app.crates_to_add.widget.crates.is_empty() {
+                let mut latest_versions = app.show_export();
+
+                let tx = app.action_tx.clone();
+                let crates = app.crates_to_add.widget.crates.clone();
+                let crates_io = app.index.clone();
+                let registries = app.registries.clone();
+                tokio::spawn(async move {
+                    let errors = find_latest_versions(
+                        &crates,
+                        &mut latest_versions,
+                        &crates_io,
+                        &registries,
+                    )
+                    .await;
+                    tx.send(Action::UpdateExportVersions(latest_versions, errors))
+                        .unwrap_or(());
+                });
+            }
+        }
+        Action::UpdateExportVersions(latest_versions, errors) => {
+            app.load_export_versions(latest_versions, errors);
+        }
+        Action::ToggleShowPresets => {
+            if app.is_showing_presets || !app.is_showing_popup() {
+                app.toggle_show_presets();
+            }
+        }
+        Action::ToggleWorkspaceDependencies => {
+            if app.is_showing_packages {
+                app.toggle_workspace_dependencies();
+            }
+        }
+        Action::FocusCratesToAdd => {
+            if !app.is_showing_popup() {
+                app.focus_crates_to_add();
+            }
+        }
+        Action::FocusCratesList => {
+            if !app.is_showing_popup() {
+                app.focus_crates_list();
+            }
+        }
+        Action::CycleDependencyKind => {
+            if !app.is_showing_popup() && app.is_focusing_crates_to_add() {
+                app.cycle_dependency_kind();
+            }
+        }
+        Action::CycleVersionRequirement => {
+            if app.is_showing_versions {
+                app.cycle_version_requirement();
+            }
+        }
+        Action::PreviewDependencies => {
+            if app.is_saving_profile() {
+                app.close_save_profile();
+            } else if app.is_showing_presets {
+                app.apply_highlighted_preset();
+            } else if app.is_showing_preview {
+                //Enter on the preview confirms the changes
+                if !app.is_loading_preview() {
+                    app.close_preview();
+                    app.action_tx.send(Action::ShowLoadingAddingDeps).unwrap();
+                }
+            } else if !app.is_showing_popup() && !app.crates_to_add.widget.crates.is_empty() {
+                //In a workspace the user first says which packages get the crates
+                if !app.has_target_packages() {
+                    app.toggle_show_packages();
+                    return;
+                }
 
-            tokio::spawn(async move {
-                tokio::time::sleep(Duration::from_millis(1200)).await;
-                tx.send(Action::Quit).unwrap();
-            });
+                app.show_preview_loading();
+
+                let tx = app.action_tx.clone();
+                let deps_builder =
+                    DependenciesBuilder::new(app.crates_to_add.widget.crates.clone())
+                        .target(app.workspace_target());
+
+                tokio::spawn(async move {
+                    let previewing = deps_builder.clone();
+                    let previews = tokio::task::spawn_blocking(move || previewing.preview())
+                        .await
+                        .unwrap_or_else(|e| {
+                            deps_builder.failed_previews(&format!("cargo add stopped: {e}"))
+                        });
+                    tx.send(Action::UpdatePreview(previews)).unwrap_or(());
+                });
+            }
+        }
+        Action::UpdatePreview(previews) => {
+            if app.is_showing_preview {
+                app.load_preview(previews);
+            }
+        }
+        Action::ShowAddResults(report) => app.show_add_report(report),
+        Action::RestoreManifest | Action::PageUp | Action::PageDown => {}
+
+        Action::StartSearch => {
+            if !app.is_showing_popup() && !app.is_focusing_crates_to_add() {
+                app.start_search();
+            }
+        }
+        Action::TypeSearch(character) => app.type_search(character),
+        Action::DeleteSearchCharacter => app.delete_search_character(),
+        Action::ConfirmSearch => app.confirm_search(),
+        Action::CloseSearch => app.close_search(),
+
+        Action::TypeProfileName(character) => app.type_profile_name(character),
+        Action::DeleteProfileNameCharacter => app.delete_profile_name_character(),
+        Action::ConfirmProfileName => app.save_profile(),
+        Action::CancelProfileName => app.close_save_profile(),
+
+        Action::ReloadCatalog(crates_data) => {
+            app.load_catalog(&JsonContentParser::parse_content(&crates_data));
+            app.catalog_status = CatalogStatus::UpToDate;
+            app.action_tx.send(Action::FetchFeatures).unwrap();
         }
+        Action::CatalogUpToDate => app.catalog_status = CatalogStatus::UpToDate,
+        Action::CatalogOffline => app.set_catalog_offline(),
 
         Action::CheckDocs => app.check_docs(),
         Action::CheckCratesIo => app.check_crates_io(),
+        Action::ToggleShowReadme => {
+            if app.is_showing_popup() {
+                return;
+            }
+
+            if let Some((crate_name, version)) = app.show_readme() {
+                let tx = app.action_tx.clone();
+                let client = app.crates_io.http().clone();
+                tokio::spawn(async move {
+                    let readme = fetch_readme(&client, &crate_name, &version)
+                        .await
+                        .map_err(|e| e.to_string());
+                    tx.send(Action::UpdateReadme(crate_name, readme))
+                        .unwrap_or(());
+                });
+            }
+        }
+        Action::UpdateReadme(crate_name, readme) => app.load_readme(&crate_name, readme),
         Action::ScrollPreviousCategory => {
-            if !app.is_showing_features {
+            if !app.is_showing_popup() {
                 app.previos_category();
             }
         }
         Action::ScrollNextCategory => {
-            if !app.is_showing_features {
+            if !app.is_showing_popup() {
                 app.next_category();
             }
         }
         Action::ToggleOne => {
-            if app.is_showing_features {
+            if app.is_showing_preview {
+                app.remove_crate_from_preview();
+            } else if app.is_showing_packages {
+                app.toggle_select_package();
+            } else if app.is_showing_versions {
+                app.toggle_select_version();
+            } else if app.is_showing_features {
                 app.toggle_select_one_feature();
+            } else if app.is_focusing_crates_to_add() {
+                app.remove_highlighted_crate_to_add();
             } else {
                 app.toggle_select_dependencie();
                 app.push_or_remove_selected_crates();
             }
         }
+        Action::ToggleDefaultFeatures => {
+            if app.is_showing_features {
+                app.toggle_default_features();
+            }
+        }
+        Action::ToggleSection => {
+            if !app.is_showing_popup() {
+                app.toggle_section();
+            }
+        }
         Action::ToggleAll => {
-            if !app.is_showing_features {
+            if !app.is_showing_popup() {
                 app.toggle_select_all_dependencies();
                 app.push_or_remove_selected_crates();
             }
@@ -85,15 +348,35 @@ pub fn update(app: &mut App, action: Action) {
             app.on_tick();
         }
         Action::ScrollUp => {
-            if app.is_showing_features {
+            if app.is_showing_preview {
+                app.scroll_up_preview();
+            } else if app.is_showing_presets {
+                app.scroll_up_presets();
+            } else if app.is_showing_packages {
+                app.scroll_up_packages();
+            } else if app.is_showing_versions {
+                app.scroll_up_versions();
+            } else if app.is_showing_features {
                 app.scroll_up_features();
+            } else if app.is_focusing_crates_to_add() {
+                app.scroll_up_crates_to_add();
             } else {
                 app.scroll_up();
             }
         }
         Action::ScrollDown => {
-            if app.is_showing_features {
+            if app.is_showing_preview {
+                app.scroll_down_preview();
+            } else if app.is_showing_presets {
+                app.scroll_down_presets();
+            } else if app.is_showing_packages {
+                app.scroll_down_packages();
+            } else if app.is_showing_versions {
+                app.scroll_down_versions();
+            } else if app.is_showing_features {
                 app.scroll_down_features();
+            } else if app.is_focusing_crates_to_add() {
+                app.scroll_down_crates_to_add();
             } else {
                 app.scroll_down();
             }
@@ -111,147 +394,95 @@ pub fn update(app: &mut App, action: Action) {
         Action::AddingDeps => {
             let tx = app.action_tx.clone();
 
-            let deps_builder = DependenciesBuilder::new(app.crates_to_add.widget.crates.clone());
+            let deps_builder = DependenciesBuilder::new(app.crates_to_add.widget.crates.clone())
+                .target(app.workspace_target());
 
             tokio::spawn(async move {
-                match deps_builder.add_dependencies() {
-                    Ok(()) => {
-                        tx.send(Action::ShowAddingDependenciesOperation).unwrap();
-                    }
-                    Err(e) => panic!("An Error ocurred, please report it on github: https://github.com/josueBarretogit/get_blessed_rs \n
-                    details: {e}"),
-                }
+                //Taken here so Cargo.toml can be restored even if adding the crates panics
+                let backup = deps_builder.backup();
+                let adding = deps_builder.clone();
+                let backup_for_adding = backup.clone();
+
+                let report = tokio::task::spawn_blocking(move || {
+                    adding.add_dependencies_after(backup_for_adding)
+                })
+                .await
+                .unwrap_or_else(|e| {
+                    deps_builder.failed_report(&format!("cargo add stopped: {e}"), backup)
+                });
+
+                tx.send(Action::ShowAddResults(report)).unwrap_or(());
             });
         }
         Action::FetchFeatures => {
-            let user_agent = format!(
-                "get-blessed/v{} ({} {} {})",
-                env!("CARGO_PKG_VERSION"),
-                env::consts::FAMILY,
-                env::consts::OS,
-                env::consts::ARCH
-            );
-
-            let client = Arc::new(
-                crates_io_api::AsyncClient::new(&user_agent, Duration::from_millis(100)).unwrap(),
-            );
-
-            fetch_features(
-                &app.general_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::General,
-            );
-
-            fetch_features(
-                &app.common_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Common,
-            );
-
-            fetch_features(
-                &app.ffi_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::FFI,
-            );
-
-            fetch_features(
-                &app.math_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Math,
-            );
-
-            fetch_features(
-                &app.clis_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Clis,
-            );
+            // A crate can be listed in more than one category, only ask for its features once
+            let crates: BTreeMap<String, Option<String>> = app
+                .categories
+                .iter()
+                .flat_map(|category| {
+                    category
+                        .crates
+                        .iter()
+                        .map(|krate| (krate.name.clone(), krate.registry.clone()))
+                })
+                .collect();
 
             fetch_features(
-                &app.graphics_crates,
+                crates,
                 &app.action_tx,
-                &client,
-                CategoriesWidget::Graphics,
-            );
-
-            fetch_features(
-                &app.networking_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Networking,
-            );
-
-            fetch_features(
-                &app.database_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Databases,
-            );
-
-            fetch_features(
-                &app.cryptography_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Cryptography,
+                &app.index,
+                &app.crates_io,
+                &app.registries,
             );
+        }
 
-            fetch_features(
-                &app.concurrency_crates,
-                &app.action_tx,
-                &client,
-                CategoriesWidget::Concurrency,
-            );
+        Action::UpdateDetails(crate_name, details) => {
+            app.load_details(&crate_name, &details);
         }
 
-        Action::UpdateFeatures(category, features, crate_index_to_update) => match category {
-            CategoriesWidget::General => {
-                load_features(&mut app.general_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::Common => {
-                load_features(&mut app.common_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::FFI => {
-                load_features(&mut app.ffi_crates, crate_index_to_update, features);
-            }
+        Action::UpdateVersions(crate_name, versions) => {
+            app.load_versions(&crate_name, versions.as_deref());
+        }
 
-            CategoriesWidget::Math => {
-                load_features(&mut app.math_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::Clis => {
-                load_features(&mut app.clis_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::Graphics => {
-                load_features(&mut app.graphics_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::Databases => {
-                load_features(&mut app.database_crates, crate_index_to_update, features);
-            }
-            CategoriesWidget::Networking => {
-                load_features(&mut app.networking_crates, crate_index_to_update, features);
-            }
+        Action::UpdateFeatures(crate_name, features) => {
+            app.load_features(&crate_name, features.as_deref());
+        }
 
-            CategoriesWidget::Concurrency => {
-                load_features(&mut app.concurrency_crates, crate_index_to_update, features);
+        Action::Quit => {
+            if app.is_showing_preview {
+                app.close_preview();
+            } else if app.is_showing_presets {
+                app.toggle_show_presets();
+            } else if app.is_saving_profile() {
+                app.close_save_profile();
+            } else if app.is_showing_features {
+                //Closing them like their own key does keeps the crate selected if it was customized
+                update(app, Action::ToggleShowFeatures);
+            } else if app.is_showing_versions {
+                update(app, Action::ToggleShowVersions);
+            } else if app.is_showing_packages {
+                app.toggle_show_packages();
+            } else if app.is_searching() && !app.is_showing_popup() {
+                app.close_search();
+            } else {
+                app.exit();
             }
-            CategoriesWidget::Cryptography => load_features(
-                &mut app.cryptography_crates,
-                crate_index_to_update,
-                features,
-            ),
-        },
-
-        Action::Quit => app.exit(),
+        }
     }
 }
-pub fn handle_event(tx: UnboundedSender<Action>) -> tokio::task::JoinHandle<()> {
+pub fn handle_event(
+    tx: UnboundedSender<Action>,
+    is_typing_search: Arc<AtomicBool>,
+    is_typing_profile_name: Arc<AtomicBool>,
+) -> tokio::task::JoinHandle<()> {
     let tick_rate = std::time::Duration::from_millis(250);
     tokio::spawn(async move {
         loop {
-            let action = user_actions(tick_rate);
+            let action = user_actions(
+                tick_rate,
+                is_typing_search.load(Ordering::Relaxed),
+                is_typing_profile_name.load(Ordering::Relaxed),
+            );
             if tx.send(action).is_err() {
                 break;
             }
@@ -259,19 +490,55 @@ pub fn handle_event(tx: UnboundedSender<Action>) -> tokio::task::JoinHandle<()>
     })
 }
 
-pub async fn run() -> Result<(), Box<dyn Error>> {
+pub async fn run(
+    source: CatalogSource,
+    team_catalogs: Vec<CratesData>,
+    presets: Vec<Preset>,
+) -> Result<(), Box<dyn Error>> {
     let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
 
     let (action_tx, mut action_rx) = mpsc::unbounded_channel::<Action>();
 
-    let json_parser = JsonContentParser::parse_content().await;
+    // Show the cached crates list right away and refresh it in the background, only wait for the
+    // download when there is nothing cached yet. Local sources are always read directly
+    let cached = match &source {
+        CatalogSource::Url(url) => load_catalog(url),
+        CatalogSource::File(_) | CatalogSource::Stdin => None,
+    };
 
-    let mut app = App::setup(action_tx.clone(), &json_parser);
+    let (crates_data, catalog_status) = if let Some(cached) = cached {
+        (cached.data, CatalogStatus::Refreshing(cached.fetched_at))
+    } else {
+        let crates_data = load_crates_data(&source)
+            .await
+            .map_err(|e| format!("Could not load the crates list from {source}: {e}"))?;
+        if let CatalogSource::Url(url) = &source {
+            save_catalog(url, &crates_data).ok();
+        }
+        (crates_data, CatalogStatus::UpToDate)
+    };
 
-    let task = handle_event(app.action_tx.clone());
+    let json_parser =
+        JsonContentParser::parse_content(&merge_team_catalogs(crates_data.clone(), &team_catalogs));
+
+    let mut app = App::setup(action_tx.clone(), &json_parser, catalog_status)
+        .map_err(|e| format!("Could not start the crates.io client: {e}"))?;
+
+    app.set_workspace(Workspace::detect().ok());
+    app.set_presets(presets, load_profiles());
+
+    let task = handle_event(
+        app.action_tx.clone(),
+        app.typing_search_flag(),
+        app.typing_profile_name_flag(),
+    );
 
     action_tx.send(Action::FetchFeatures).unwrap();
 
+    if let (CatalogStatus::Refreshing(_), CatalogSource::Url(url)) = (catalog_status, source) {
+        refresh_catalog(url, crates_data, team_catalogs, &action_tx);
+    }
+
     loop {
         terminal.draw(|f| {
             let area = f.size();
@@ -292,47 +559,242 @@ pub async fn run() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+fn refresh_catalog(
+    url: String,
+    cached_data: CratesData,
+    team_catalogs: Vec<CratesData>,
+    tx: &UnboundedSender<Action>,
+) {
+    let tx = tx.clone();
+    tokio::spawn(async move {
+        match scrape_site(&url).await {
+            Ok(crates_data) => {
+                save_catalog(&url, &crates_data).ok();
+                if crates_data == cached_data {
+                    tx.send(Action::CatalogUpToDate).unwrap_or(());
+                } else {
+                    tx.send(Action::ReloadCatalog(merge_team_catalogs(
+                        crates_data,
+                        &team_catalogs,
+                    )))
+                    .unwrap_or(());
+                }
+            }
+            Err(_) => tx.send(Action::CatalogOffline).unwrap_or(()),
+        }
+    });
+}
+
+///The versions and features come from the index, which answers fast. The details pane needs
+///crates.io's API, its answers cached on disk are shown right away and it is only asked again
+///once they are old. `crates` maps every crate to its registry, `None` for crates.io
 fn fetch_features(
-    crates: &[CrateItemList],
+    crates: BTreeMap<String, Option<String>>,
     tx: &UnboundedSender<Action>,
-    client: &Arc<crates_io_api::AsyncClient>,
-    category: CategoriesWidget,
+    index: &IndexClient,
+    crates_io: &CratesIoClient,
+    registries: &Registries,
 ) {
-    for (index, crateitem) in crates.iter().enumerate() {
-        let crate_name = crateitem.name.clone();
+    for (crate_name, registry) in crates {
+        if let Some(registry) = registry {
+            fetch_from_registry(crate_name, tx.clone(), registries.index_client(&registry));
+            continue;
+        }
+
         let tx = tx.clone();
-        let client = Arc::clone(client);
+        let index = index.clone();
+        let crates_io = crates_io.clone();
         tokio::spawn(async move {
-            let response = client.get_crate(&crate_name).await;
-            if let Ok(information) = response {
-                if let Some(latest) = information.versions.first() {
-                    let latest: Vec<FeatureItemList> = latest
-                        .features
-                        .clone()
-                        .into_keys()
-                        .map(FeatureItemList::new)
-                        .collect();
-
-                    if latest.is_empty() {
-                        tx.send(Action::UpdateFeatures(category, None, index))
-                            .unwrap_or(());
-                    } else {
-                        tx.send(Action::UpdateFeatures(category, Some(latest), index))
-                            .unwrap_or(());
-                    }
-                };
-            };
+            let from_index = index.versions(&crate_name).await.ok();
+
+            if let Some(versions) = &from_index {
+                send_versions_and_features(&tx, &crate_name, versions);
+            }
+
+            let cached = crates_io.cached(&crate_name);
+
+            if let Some(cached) = &cached {
+                send_crate_information(&tx, &crate_name, &cached.response, from_index.is_none());
+                if cached.is_fresh() {
+                    return;
+                }
+            }
+
+            if let Ok(Some(information)) = crates_io.fetch(&crate_name, cached.as_ref()).await {
+                send_crate_information(&tx, &crate_name, &information, from_index.is_none());
+            }
         });
     }
 }
 
-///These are the actions / commands the user can do
-fn user_actions(tick_rate: Duration) -> Action {
+///crates.io knows nothing about the crates of other registries, their index is all there is.
+///`index` is `None` when the registry is not in `.cargo/config.toml`
+fn fetch_from_registry(
+    crate_name: String,
+    tx: UnboundedSender<Action>,
+    index: Option<IndexClient>,
+) {
+    tokio::spawn(async move {
+        if let Some(index) = index {
+            match index.versions(&crate_name).await {
+                Ok(versions) if !versions.is_empty() => {
+                    send_versions_and_features(&tx, &crate_name, &versions);
+                    return;
+                }
+                _ => {}
+            }
+        }
+
+        // Otherwise the crate would stay loading forever
+        tx.send(Action::UpdateFeatures(crate_name, None))
+            .unwrap_or(());
+    });
+}
+
+///The versions and features are only taken from crates.io's API when the index could not be read
+fn send_crate_information(
+    tx: &UnboundedSender<Action>,
+    crate_name: &str,
+    information: &CrateResponse,
+    with_versions: bool,
+) {
+    let versions: Vec<IndexVersion> = information
+        .versions
+        .iter()
+        .map(|version| {
+            IndexVersion::new(
+                version.num.clone(),
+                version.features.clone(),
+                version.yanked,
+                version.rust_version.clone(),
+                Some(version.created_at),
+            )
+        })
+        .collect();
+
+    if with_versions {
+        send_versions_and_features(tx, crate_name, &versions);
+    }
+
+    // The details shown are the ones of the version cargo add picks by default
+    let latest = latest_stable(&versions).and_then(|latest| {
+        information
+            .versions
+            .iter()
+            .find(|version| version.num == latest.num)
+    });
+
+    let details = CrateDetails {
+        latest_version: latest.map_or(information.crate_data.max_version.clone(), |latest| {
+            latest.num.clone()
+        }),
+        downloads: information.crate_data.downloads,
+        recent_downloads: information.crate_data.recent_downloads,
+        updated_at: information.crate_data.updated_at,
+        license: latest.and_then(|latest| latest.license.clone()),
+        repository: information.crate_data.repository.clone(),
+        homepage: information.crate_data.homepage.clone(),
+        rust_version: latest.and_then(|latest| latest.rust_version.clone()),
+    };
+
+    tx.send(Action::UpdateDetails(crate_name.to_string(), details))
+        .unwrap_or(());
+}
+
+///`versions` go from the newest to the oldest, the features shown are the ones of the version
+///cargo add picks by default
+fn send_versions_and_features(
+    tx: &UnboundedSender<Action>,
+    crate_name: &str,
+    versions: &[IndexVersion],
+) {
+    let version_items: Vec<VersionItemList> = versions
+        .iter()
+        .map(|version| VersionItemList::new(version.num.clone(), version.pubtime, version.yanked))
+        .collect();
+
+    tx.send(Action::UpdateVersions(
+        crate_name.to_string(),
+        Some(version_items),
+    ))
+    .unwrap_or(());
+
+    if let Some(latest) = latest_stable(versions) {
+        let default_features = enabled_by_default(&latest.features);
+
+        let mut features: Vec<FeatureItemList> = latest
+            .features
+            .clone()
+            .into_iter()
+            .map(|(feature, enables)| FeatureItemList {
+                is_default: default_features.contains(&feature),
+                enables,
+                ..FeatureItemList::new(feature)
+            })
+            .collect();
+
+        features.sort();
+
+        if features.is_empty() {
+            tx.send(Action::UpdateFeatures(crate_name.to_string(), None))
+                .unwrap_or(());
+        } else {
+            tx.send(Action::UpdateFeatures(
+                crate_name.to_string(),
+                Some(features),
+            ))
+            .unwrap_or(());
+        }
+    }
+}
+
+///Whether the user pressed a key, the other actions come from what is fetched in the background
+fn is_from_user(action: &Action) -> bool {
+    !matches!(
+        action,
+        Action::ReloadCatalog(_)
+            | Action::CatalogUpToDate
+            | Action::CatalogOffline
+            | Action::FetchFeatures
+            | Action::UpdateFeatures(..)
+            | Action::UpdateVersions(..)
+            | Action::UpdateDetails(..)
+            | Action::UpdateReadme(..)
+            | Action::UpdateExportVersions(..)
+            | Action::Tick
+    )
+}
+
+///These are the actions / commands the user can do, while typing a search or the name of a
+///profile the keys are typed into it instead
+fn user_actions(
+    tick_rate: Duration,
+    is_typing_search: bool,
+    is_typing_profile_name: bool,
+) -> Action {
     if poll(tick_rate).unwrap() {
         if let Event::Key(key) = event::read().unwrap() {
-            if key.kind == KeyEventKind::Press {
+            if key.kind == KeyEventKind::Press && is_typing_profile_name {
+                match key.code {
+                    KeyCode::Char(character) => Action::TypeProfileName(character),
+                    KeyCode::Backspace => Action::DeleteProfileNameCharacter,
+                    KeyCode::Enter => Action::ConfirmProfileName,
+                    KeyCode::Esc => Action::CancelProfileName,
+                    _ => Action::Tick,
+                }
+            } else if key.kind == KeyEventKind::Press && is_typing_search {
+                match key.code {
+                    KeyCode::Char(character) => Action::TypeSearch(character),
+                    KeyCode::Backspace => Action::DeleteSearchCharacter,
+                    KeyCode::Enter => Action::ConfirmSearch,
+                    KeyCode::Esc => Action::CloseSearch,
+                    KeyCode::Down => Action::ScrollDown,
+                    KeyCode::Up => Action::ScrollUp,
+                    _ => Action::Tick,
+                }
+            } else if key.kind == KeyEventKind::Press {
                 match key.code {
-                    KeyCode::Enter => Action::ShowLoadingAddingDeps,
+                    KeyCode::Enter => Action::PreviewDependencies,
                     KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
                     KeyCode::Tab => Action::ScrollNextCategory,
                     KeyCode::BackTab => Action::ScrollPreviousCategory,
@@ -340,9 +802,26 @@ fn user_actions(tick_rate: Duration) -> Action {
                     KeyCode::Up | KeyCode::Char('k') => Action::ScrollUp,
                     KeyCode::Char('a') => Action::ToggleAll,
                     KeyCode::Char('s') => Action::ToggleOne,
+                    KeyCode::Char(' ') => Action::ToggleSection,
                     KeyCode::Char('d') => Action::CheckDocs,
                     KeyCode::Char('c') => Action::CheckCratesIo,
                     KeyCode::Char('f') => Action::ToggleShowFeatures,
+                    KeyCode::Char('r') => Action::RestoreManifest,
+                    KeyCode::Char('n') => Action::ToggleDefaultFeatures,
+                    KeyCode::Char('v') => Action::ToggleShowVersions,
+                    KeyCode::Char('o') => Action::CycleVersionRequirement,
+                    KeyCode::Right | KeyCode::Char('l') => Action::FocusCratesToAdd,
+                    KeyCode::Left | KeyCode::Char('h') => Action::FocusCratesList,
+                    KeyCode::Char('t') => Action::CycleDependencyKind,
+                    KeyCode::Char('w') => Action::ToggleShowPackages,
+                    KeyCode::Char('p') => Action::ToggleShowPresets,
+                    KeyCode::Char('S') => Action::SaveProfile,
+                    KeyCode::Char('e') => Action::ToggleExport,
+                    KeyCode::Char('u') => Action::ToggleWorkspaceDependencies,
+                    KeyCode::Char('/') => Action::StartSearch,
+                    KeyCode::Char('i') => Action::ToggleShowReadme,
+                    KeyCode::PageDown => Action::PageDown,
+                    KeyCode::PageUp => Action::PageUp,
                     _ => Action::Tick,
                 }
             } else {
@@ -1,22 +1,22 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     widgets::ListState,
 };
 
 use crate::{
-    dependency_builder::CrateToAdd,
+    dependency_builder::{Change, CrateToAdd},
     view::{
         app::App,
-        widgets::{CategoriesWidget, CrateItemList, FeatureItemList, ItemListStatus},
+        widgets::{CrateItemList, FeatureItemList, ItemListStatus},
     },
 };
 
+///Crates already in Cargo.toml are left as they are, they are only removed one by one
 pub fn toggle_status_all(crates: &mut [CrateItemList]) {
-    for item in crates {
-        item.status = match item.status {
-            ItemListStatus::Selected => ItemListStatus::Unselected,
-            ItemListStatus::Unselected => ItemListStatus::Selected,
-        };
+    for item in crates.iter_mut().filter(|item| item.existing.is_none()) {
+        toggle_status_one_crate(item);
     }
 }
 
@@ -33,34 +33,24 @@ pub fn toggle_status_one_crate(crate_selected: &mut CrateItemList) {
 
 pub fn push_or_remove_crates(crates_to_add: &mut Vec<CrateToAdd>, crates: &[CrateItemList]) {
     for krate in crates {
-        match krate.status {
-            ItemListStatus::Selected => {
-                let crate_to_push_or_update = crates_to_add
-                    .iter()
-                    .position(|crate_to_add| crate_to_add.crate_name == krate.name);
-
-                // If the crate selected is already in the list then update the features
-                match crate_to_push_or_update {
-                    // Update the features
-                    Some(index) => {
-                        crates_to_add[index].features = krate.features.as_ref().map(|feat| {
-                            feat.iter()
-                                .filter_map(|feature| {
-                                    if feature.status == ItemListStatus::Selected {
-                                        Some(feature.name.to_string())
-                                    } else {
-                                        None
-                                    }
-                                })
-                                .collect()
-                        });
-                    }
-                    None => crates_to_add.push(CrateToAdd::from(krate)),
-                }
-            }
-            ItemListStatus::Unselected => {
-                crates_to_add.retain(|crate_to_add| crate_to_add.crate_name != krate.name);
+        // Crates already in Cargo.toml are selected, they are listed once the user changes them
+        let is_listed = match krate.existing {
+            Some(_) => krate.is_changed(),
+            None => krate.status == ItemListStatus::Selected,
+        };
+
+        if is_listed {
+            let crate_to_push_or_update = crates_to_add
+                .iter()
+                .position(|crate_to_add| crate_to_add.crate_name == krate.name);
+
+            // If the crate selected is already in the list then update its features
+            match crate_to_push_or_update {
+                Some(index) => crates_to_add[index] = CrateToAdd::from(krate),
+                None => crates_to_add.push(CrateToAdd::from(krate)),
             }
+        } else {
+            crates_to_add.retain(|crate_to_add| crate_to_add.crate_name != krate.name);
         }
     }
 }
@@ -83,52 +73,94 @@ pub fn toggle_one_feature(current_crate: &mut CrateItemList, features_list_state
             ItemListStatus::Unselected => {
                 current_feature_selected.status = ItemListStatus::Selected;
             }
-        };
-    };
+        }
+    }
 }
 
-pub fn select_crate_if_features_are_selected(app: &mut App) {
-    if let Some((crate_selected, index_current_crate)) = app.get_current_crate_selected() {
-        if crate_selected.features.as_ref().is_some_and(|features| {
-            features
-                .iter()
-                .any(|feature| feature.status == ItemListStatus::Selected)
-        }) && crate_selected.status != ItemListStatus::Selected
-        {
-            match app.crate_categories.widget {
-                CategoriesWidget::General => {
-                    app.general_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Common => {
-                    app.common_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::FFI => {
-                    app.ffi_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Math => {
-                    app.math_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Clis => {
-                    app.clis_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Graphics => {
-                    app.graphics_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Databases => {
-                    app.database_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Networking => {
-                    app.networking_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Concurrency => {
-                    app.concurrency_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-                CategoriesWidget::Cryptography => {
-                    app.cryptography_crates[index_current_crate].status = ItemListStatus::Selected;
-                }
-            }
+///Choosing the features or the version of a crate means the user wants to add it
+pub fn select_crate_if_customized(app: &mut App) {
+    if let Some(crate_selected) = app.current_crate_mut() {
+        let customized = crate_selected.version.is_some()
+            || !crate_selected.default_features
+            || crate_selected.features.as_ref().is_some_and(|features| {
+                features
+                    .iter()
+                    .any(|feature| feature.status == ItemListStatus::Selected)
+            });
+
+        if customized {
+            crate_selected.status = ItemListStatus::Selected;
+        }
+    }
+}
+
+///How well `query` matches `text` when its characters appear in it in the same order, `None`
+///when they do not. Characters next to each other or starting a word score higher
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut start = 0;
+
+    for character in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = start + text.get(start..)?.iter().position(|c| *c == character)?;
+
+        score += 1;
+        if start > 0 && found == start {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
         }
+
+        start = found + 1;
+    }
+
+    Some(score)
+}
+
+///Crates are searched by name and description, the name matters the most. A long description
+///has most letters somewhere, so there the query has to be found mostly in one piece
+pub fn crate_search_score(query: &str, krate: &CrateItemList) -> Option<u32> {
+    let name_score = fuzzy_score(query, &krate.name).map(|score| {
+        let prefix_bonus = if krate.name.starts_with(&query.to_lowercase()) {
+            20
+        } else {
+            0
+        };
+        score * 2 + prefix_bonus
+    });
+
+    let query_len = query.chars().filter(|c| !c.is_whitespace()).count();
+    let min_description_score = u32::try_from(query_len * 5).unwrap_or(u32::MAX);
+
+    let description_score =
+        fuzzy_score(query, &krate.description).filter(|score| *score + 5 >= min_description_score);
+
+    name_score.max(description_score)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollDirection {
+    Up,
+    Down,
+}
+
+///Moves the highlight of a list of `len` items, from the last item it goes back to the first and
+///from the first to the last
+pub fn scroll_wrapping(state: &mut ListState, len: usize, direction: ScrollDirection) {
+    if len == 0 {
+        return;
     }
+
+    let next = state.selected().map_or(0, |index| {
+        let index = index.min(len - 1);
+        match direction {
+            ScrollDirection::Down => (index + 1) % len,
+            ScrollDirection::Up => (index + len - 1) % len,
+        }
+    });
+
+    state.select(Some(next));
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -149,12 +181,228 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 pub fn load_features(
-    crates_list: &mut [CrateItemList],
-    index_crate_to_update: usize,
+    crate_to_update: &mut CrateItemList,
     features: Option<Vec<FeatureItemList>>,
+    crates_to_add: &[CrateToAdd],
 ) {
-    crates_list[index_crate_to_update].is_loading = false;
-    if let Some(feat) = features {
-        crates_list[index_crate_to_update].features = Some(feat);
+    crate_to_update.is_loading = false;
+    if let Some(mut feat) = features {
+        // Keep the features the user chose before the list of crates was reloaded, otherwise the
+        // ones the crate has in Cargo.toml
+        if let Some(selected_features) = crates_to_add
+            .iter()
+            .find(|crate_to_add| crate_to_add.crate_name == crate_to_update.name)
+            .and_then(|crate_to_add| crate_to_add.features.as_ref())
+            .or(crate_to_update
+                .existing
+                .as_ref()
+                .map(|existing| &existing.features))
+        {
+            for feature in &mut feat {
+                if selected_features.contains(&feature.name) {
+                    feature.status = ItemListStatus::Selected;
+                }
+            }
+        }
+        crate_to_update.features = Some(feat);
+    }
+}
+
+/// Marks as selected the crates which are already in the list of crates to add
+pub fn select_crates_to_add(crates: &mut [CrateItemList], crates_to_add: &[CrateToAdd]) {
+    for krate in crates {
+        if let Some(crate_to_add) = crates_to_add
+            .iter()
+            .find(|crate_to_add| crate_to_add.crate_name == krate.name)
+        {
+            krate.status = match crate_to_add.change {
+                Change::Remove => ItemListStatus::Unselected,
+                Change::Add | Change::Update { .. } => ItemListStatus::Selected,
+            };
+            krate.default_features = crate_to_add.default_features;
+            krate.version.clone_from(&crate_to_add.version);
+        }
+    }
+}
+
+///The features the `default` feature turns on, directly or through other features
+pub fn enabled_by_default(features: &HashMap<String, Vec<String>>) -> BTreeSet<String> {
+    enabled_by(features, ["default"]).into_keys().collect()
+}
+
+///The features turned on by the ones in `roots`, directly or through other features, with the
+///feature of `roots` that turns each one on
+pub fn enabled_by<'a>(
+    features: &HashMap<String, Vec<String>>,
+    roots: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<String, String> {
+    let mut enabled = BTreeMap::new();
+
+    for root in roots {
+        let mut pending = vec![root.to_string()];
+
+        while let Some(feature) = pending.pop() {
+            for enables in features.get(&feature).into_iter().flatten() {
+                // `dep:name` and `crate/feature` are not features of this crate
+                if features.contains_key(enables) && !enabled.contains_key(enables) {
+                    enabled.insert(enables.clone(), root.to_string());
+                    pending.push(enables.clone());
+                }
+            }
+        }
+    }
+
+    enabled
+}
+
+///The version a requirement like `=1.0.100` or `~1.0.100` was made from
+pub fn requirement_version(requirement: &str) -> &str {
+    requirement.trim_start_matches(['=', '~', '^'])
+}
+
+///Goes from the default requirement `1.0.100`, which allows compatible updates, to pinning the
+///exact version `=1.0.100` and to allowing only patch updates `~1.0.100`
+pub fn cycle_requirement(requirement: &str) -> String {
+    let version = requirement_version(requirement);
+
+    match requirement.chars().next() {
+        Some('=') => format!("~{version}"),
+        Some('~') => version.to_string(),
+        _ => format!("={version}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scrolling_wraps_around_the_list() {
+        let mut state = ListState::default();
+
+        scroll_wrapping(&mut state, 3, ScrollDirection::Up);
+        assert_eq!(state.selected(), Some(0));
+
+        scroll_wrapping(&mut state, 3, ScrollDirection::Up);
+        assert_eq!(state.selected(), Some(2));
+
+        scroll_wrapping(&mut state, 3, ScrollDirection::Down);
+        assert_eq!(state.selected(), Some(0));
+
+        scroll_wrapping(&mut state, 0, ScrollDirection::Down);
+        assert_eq!(state.selected(), Some(0));
+    }
+
+    #[test]
+    fn crates_already_added_are_only_removed_one_by_one() {
+        let mut crates = vec![
+            CrateItemList {
+                name: "serde".to_string(),
+                existing: Some(crate::project::ExistingDependency::default()),
+                ..CrateItemList::default()
+            },
+            CrateItemList {
+                name: "rand".to_string(),
+                ..CrateItemList::default()
+            },
+        ];
+
+        toggle_status_all(&mut crates);
+
+        assert_eq!(crates[0].status, ItemListStatus::Unselected);
+        assert_eq!(crates[1].status, ItemListStatus::Selected);
+
+        let mut crates_to_add = Vec::new();
+        push_or_remove_crates(&mut crates_to_add, &crates);
+
+        assert_eq!(crates_to_add.len(), 2);
+        assert_eq!(crates_to_add[0].change, Change::Remove);
+        assert_eq!(crates_to_add[1].change, Change::Add);
+
+        crates[0].status = ItemListStatus::Selected;
+        push_or_remove_crates(&mut crates_to_add, &crates);
+
+        assert_eq!(crates_to_add.len(), 1);
+    }
+
+    #[test]
+    fn crates_are_found_by_fuzzy_name_or_description() {
+        let tracing = CrateItemList {
+            name: "tracing".to_string(),
+            description: "Application level tracing framework".to_string(),
+            ..CrateItemList::default()
+        };
+        let log = CrateItemList {
+            name: "log".to_string(),
+            description: "A lightweight logging facade".to_string(),
+            ..CrateItemList::default()
+        };
+
+        assert!(fuzzy_score("trcng", "tracing").is_some());
+        assert!(fuzzy_score("gnicart", "tracing").is_none());
+        assert!(fuzzy_score("trac", "tracing") > fuzzy_score("trcg", "tracing"));
+
+        assert!(crate_search_score("trac", &tracing) > crate_search_score("trac", &log));
+        assert!(crate_search_score("logging", &log).is_some());
+        // Scattered letters of a long description are not a match
+        assert!(crate_search_score("flat", &log).is_none());
+    }
+
+    #[test]
+    fn default_features_are_followed_transitively() {
+        let features: HashMap<String, Vec<String>> = [
+            ("default", vec!["std"]),
+            ("std", vec!["alloc", "serde?/std"]),
+            ("alloc", vec![]),
+            ("derive", vec!["dep:serde_derive"]),
+        ]
+        .into_iter()
+        .map(|(name, enables)| {
+            (
+                name.to_string(),
+                enables.into_iter().map(ToString::to_string).collect(),
+            )
+        })
+        .collect();
+
+        assert_eq!(
+            enabled_by_default(&features),
+            BTreeSet::from(["alloc".to_string(), "std".to_string()])
+        );
+    }
+
+    #[test]
+    fn selected_features_turn_on_the_ones_they_enable() {
+        let features: HashMap<String, Vec<String>> = [
+            ("full", vec!["net", "fs"]),
+            ("net", vec!["io", "dep:socket2"]),
+            ("fs", vec![]),
+            ("io", vec![]),
+            ("time", vec![]),
+        ]
+        .into_iter()
+        .map(|(name, enables)| {
+            (
+                name.to_string(),
+                enables.into_iter().map(ToString::to_string).collect(),
+            )
+        })
+        .collect();
+
+        let enabled = enabled_by(&features, ["full", "time"]);
+
+        assert_eq!(
+            enabled.keys().collect::<Vec<_>>(),
+            ["fs", "io", "net"].iter().collect::<Vec<_>>()
+        );
+        assert_eq!(enabled["io"], "full");
+    }
+
+    #[test]
+    fn requirements_cycle_between_caret_exact_and_tilde() {
+        assert_eq!(cycle_requirement("1.0.100"), "=1.0.100");
+        assert_eq!(cycle_requirement("=1.0.100"), "~1.0.100");
+        assert_eq!(cycle_requirement("~1.0.100"), "1.0.100");
+        assert_eq!(requirement_version("~1.0.100"), "1.0.100");
     }
 }
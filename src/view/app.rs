@@ -1,4 +1,14 @@
 #![allow(clippy::too_many_lines)]
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+    env,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
 use throbber_widgets_tui::{Throbber, ThrobberState};
 use tokio::sync::mpsc::UnboundedSender;
 
@@ -13,20 +23,38 @@ use ratatui::{
 use type_utilities::bool::methods::Toggle;
 
 use crate::{
-    backend::{Categories, CategoriesWithSubCategories},
+    cache::CatalogStatus,
     content_parser::ContentParser,
+    crates_io::CratesIoClient,
+    dependency_builder::{
+        AddReport, Change, CrateToAdd, DependencyKind, DependencyPreview, ManifestBackup,
+        WorkspaceTarget,
+    },
+    export::{self, ExportFormat},
+    index::{IndexClient, CRATES_IO_INDEX_URL},
+    preset::Preset,
+    profile,
+    project::ExistingDependencies,
+    readme::markdown_lines,
+    registry::Registries,
     tui::handler::Action,
     utils::{
-        centered_rect, push_or_remove_crates, toggle_one_feature, toggle_status_all,
-        toggle_status_one_crate,
+        centered_rect, crate_search_score, cycle_requirement, load_features, push_or_remove_crates,
+        requirement_version, scroll_wrapping, select_crates_to_add, toggle_one_feature,
+        toggle_status_all, toggle_status_one_crate, ScrollDirection,
     },
+    workspace::Workspace,
 };
 
 use super::widgets::{
-    CategoriesWidget, CrateItemList, CratesListWidget, CratesToAddListWidget, FeaturesWidgetList,
-    FooterInstructions, Popup,
+    AddResultsWidget, CategoriesWidget, CrateDetails, CrateDetailsWidget, CrateItemList,
+    CrateListItem, CratesListWidget, CratesToAddListWidget, DependenciesPreviewWidget,
+    ExportWidget, FeatureItemList, FeaturesWidgetList, FooterInstructions, ItemListStatus,
+    PackagesWidget, Popup, PresetsWidget, ReadmeContent, ReadmeWidget, SaveProfileWidget,
+    VersionItemList, VersionsWidgetList,
 };
 
+#[allow(clippy::struct_excessive_bools)]
 pub struct App {
     pub action_tx: UnboundedSender<Action>,
     ///These are the crates that will be added to the users's project
@@ -37,29 +65,161 @@ pub struct App {
     pub crate_categories: CategoriesList,
     ///Contains the information about the features being displayed
     features: Features,
+    ///Contains the versions of the crate being displayed
+    versions: Versions,
+    ///The list the user is moving through
+    focus: Focus,
+    ///The packages to add the crates to, `None` unless the project is a workspace with many
+    packages: Option<Packages>,
+    pub is_showing_packages: bool,
+    ///Bundles of crates the user can select at once
+    presets: Presets,
+    pub is_showing_presets: bool,
+    ///Open while the user names the profile the selection is saved as
+    save_profile: Option<SaveProfileWidget>,
     pub exit: bool,
     pub is_showing_features: bool,
-    pub general_crates: Vec<CrateItemList>,
-    pub math_crates: Vec<CrateItemList>,
-    pub ffi_crates: Vec<CrateItemList>,
-    pub cryptography_crates: Vec<CrateItemList>,
-    pub common_crates: Vec<CrateItemList>,
-    pub concurrency_crates: Vec<CrateItemList>,
-    pub networking_crates: Vec<CrateItemList>,
-    pub database_crates: Vec<CrateItemList>,
-    pub clis_crates: Vec<CrateItemList>,
-    pub graphics_crates: Vec<CrateItemList>,
+    pub is_showing_versions: bool,
+    pub is_showing_preview: bool,
+    ///What adding the selected crates would change in Cargo.toml
+    preview: Preview,
+    ///Tells the user whether the crates list is up to date or loaded from the cache
+    pub catalog_status: CatalogStatus,
+    ///The crates of every category, in the same order as the categories list
+    pub categories: Vec<CategoryCrates>,
+    ///The crates the project's Cargo.toml already has, they are marked in the crates list
+    existing_dependencies: ExistingDependencies,
+    ///What the user typed after `/`, while it is `Some` the crates list shows the crates of every
+    ///category matching it
+    search: Option<String>,
+    ///Shared with the task reading the keyboard so the keys are typed into the search instead of
+    ///being shortcuts
+    is_typing_search: Arc<AtomicBool>,
+    ///Like `is_typing_search`, for the name of the profile being saved
+    is_typing_profile_name: Arc<AtomicBool>,
     is_adding_dependencies: bool,
     popup_widget: Popup,
+    ///Shown once `cargo add` finished, `None` until then
+    add_results: Option<AddResults>,
+    ///The README of the highlighted crate, `None` unless the user opened it
+    readme: Option<Readme>,
+    ///The selected crates ready to be copied or written to a file, `None` unless the user opened it
+    export: Option<Export>,
+    ///Shared by every request to crates.io so they all wait in the same queue
+    pub crates_io: CratesIoClient,
+    ///Where the versions and features of the crates come from
+    pub index: IndexClient,
+    ///The registries of `.cargo/config.toml`, for the crates not published to crates.io
+    pub registries: Registries,
     loader_state: throbber_widgets_tui::ThrobberState,
 }
 
+///The crates the user can select from one category
+#[derive(Default, Clone)]
+pub struct CategoryCrates {
+    pub name: String,
+    pub crates: Vec<CrateItemList>,
+    ///The use cases whose crates are hidden
+    pub collapsed_sections: BTreeSet<String>,
+}
+
+///What is shown in each row of the crates list, the index is the one of the crate in the
+///category, for a section it is its first crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrateListRow {
+    Section(usize),
+    Crate(usize),
+    ///A search result, which can come from any category
+    Found {
+        category: usize,
+        index: usize,
+    },
+}
+
+impl CategoryCrates {
+    pub fn rows(&self) -> Vec<CrateListRow> {
+        let mut rows = Vec::new();
+
+        for (index, krate) in self.crates.iter().enumerate() {
+            let starts_section = index == 0 || self.crates[index - 1].use_case != krate.use_case;
+
+            if starts_section && !krate.use_case.is_empty() {
+                rows.push(CrateListRow::Section(index));
+            }
+
+            if !self.collapsed_sections.contains(&krate.use_case) {
+                rows.push(CrateListRow::Crate(index));
+            }
+        }
+
+        rows
+    }
+
+    fn section_len(&self, use_case: &str) -> usize {
+        self.crates
+            .iter()
+            .filter(|krate| krate.use_case == use_case)
+            .count()
+    }
+}
+
 #[derive(Default)]
 pub struct Features {
     widget: FeaturesWidgetList,
     state: ListState,
 }
 
+pub struct AddResults {
+    widget: AddResultsWidget,
+    backup: Option<ManifestBackup>,
+    scroll: u16,
+}
+
+pub struct Readme {
+    widget: ReadmeWidget,
+    scroll: u16,
+}
+
+pub struct Export {
+    widget: ExportWidget,
+    ///`None` while the versions the catalog does not know are looked up in the index
+    latest_versions: Option<BTreeMap<String, String>>,
+    ///Why some of them could not be looked up, shown until the export is copied or written
+    lookup_errors: Vec<String>,
+    scroll: u16,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    #[default]
+    CratesList,
+    CratesToAdd,
+}
+
+pub struct Packages {
+    widget: PackagesWidget,
+    state: ListState,
+    workspace: Workspace,
+}
+
+#[derive(Default)]
+pub struct Presets {
+    widget: PresetsWidget,
+    state: ListState,
+}
+
+#[derive(Default)]
+pub struct Versions {
+    widget: VersionsWidgetList,
+    state: ListState,
+}
+
+#[derive(Default)]
+pub struct Preview {
+    widget: DependenciesPreviewWidget,
+    state: ListState,
+}
+
 ///This struct holds the current list of crates displayed
 #[derive(Default)]
 pub struct CratesList {
@@ -74,7 +234,7 @@ pub struct CrateToAddList {
     pub state: ListState,
 }
 
-///This struct holds the list of crates to be added to user's project
+///This struct holds the list of categories, the one selected is the category being displayed
 #[derive(Default, Clone)]
 pub struct CategoriesList {
     pub widget: CategoriesWidget,
@@ -106,66 +266,102 @@ impl Widget for &mut App {
                 Constraint::Percentage(25),
             ]);
 
-        let [categories_list_area, crates_list_area, crates_to_add] = main_layout.areas(main_area);
+        let [categories_list_area, crates_list_area, side_area] = main_layout.areas(main_area);
+
+        let [details_area, crates_to_add] =
+            Layout::vertical([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(side_area);
 
         self.render_categories_list(categories_list_area, buf);
 
         self.render_main_section(crates_list_area, buf);
 
+        CrateDetailsWidget::new(self.get_current_crate_selected()).render(details_area, buf);
+
         self.render_crates_to_add_list(crates_to_add, buf);
 
-        App::render_footer_instructions(footer_area, buf);
+        self.render_footer_instructions(footer_area, buf);
 
         if self.is_showing_features {
             self.render_features_popup(area, buf);
         }
 
+        if self.is_showing_versions {
+            self.render_versions_popup(area, buf);
+        }
+
+        if self.is_showing_packages {
+            self.render_packages_popup(area, buf);
+        }
+
+        if self.is_showing_presets {
+            let center = centered_rect(80, 50, area);
+            Clear.render(center, buf);
+            StatefulWidgetRef::render_ref(
+                &self.presets.widget,
+                center,
+                buf,
+                &mut self.presets.state,
+            );
+        }
+
+        if self.is_showing_preview {
+            self.render_preview_popup(area, buf);
+        }
+
+        if let Some(save_profile) = &self.save_profile {
+            let center = centered_rect(50, 20, area);
+            Clear.render(center, buf);
+            save_profile.render(center, buf);
+        }
+
+        if let Some(readme) = self.readme.as_mut() {
+            let center = centered_rect(80, 80, area);
+            Clear.render(center, buf);
+            StatefulWidgetRef::render_ref(&readme.widget, center, buf, &mut readme.scroll);
+        }
+
+        if let Some(export) = self.export.as_mut() {
+            let center = centered_rect(80, 70, area);
+            Clear.render(center, buf);
+            StatefulWidgetRef::render_ref(&export.widget, center, buf, &mut export.scroll);
+        }
+
         if self.is_adding_dependencies {
             let center = centered_rect(60, 20, area);
             Clear.render(center, buf);
             StatefulWidgetRef::render_ref(&self.popup_widget, center, buf, &mut self.loader_state);
         }
+
+        if let Some(add_results) = self.add_results.as_mut() {
+            let center = centered_rect(70, 50, area);
+            Clear.render(center, buf);
+            StatefulWidgetRef::render_ref(
+                &add_results.widget,
+                center,
+                buf,
+                &mut add_results.scroll,
+            );
+        }
     }
 }
 
-impl App {
-    pub fn setup(action_tx: UnboundedSender<Action>, parser: &dyn ContentParser) -> Self {
-        let page_contents = parser;
+///The index lookups which failed, one per line
+fn lookup_status(lookup_errors: &[String]) -> Option<Result<String, String>> {
+    (!lookup_errors.is_empty()).then(|| Err(lookup_errors.join("\n")))
+}
 
+impl App {
+    pub fn setup(
+        action_tx: UnboundedSender<Action>,
+        parser: &dyn ContentParser,
+        catalog_status: CatalogStatus,
+    ) -> Result<Self, reqwest::Error> {
         let mut list_state = ListState::default();
-        let mut feature_list_state = ListState::default();
 
         list_state.select(Some(0));
-        feature_list_state.select(Some(0));
-
-        let general_crates: Vec<CrateItemList> = page_contents.get_general_crates().into();
-
-        let math_crates: Vec<CrateItemList> = page_contents.get_crates(&Categories::Math).into();
-        let ffi_crates: Vec<CrateItemList> = page_contents.get_crates(&Categories::FFI).into();
-
-        let cryptography_crates: Vec<CrateItemList> =
-            page_contents.get_crates(&Categories::Cryptography).into();
-
-        let common_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Common)
-            .into();
-        let concurrency_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Concurrency)
-            .into();
-        let networking_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Networking)
-            .into();
-        let database_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Databases)
-            .into();
-        let clis_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Clis)
-            .into();
-        let graphics_crates: Vec<CrateItemList> = page_contents
-            .get_crates_with_sub(&CategoriesWithSubCategories::Graphics)
-            .into();
-
-        Self {
+
+        let mut app = Self {
             action_tx,
             crates_to_add: CrateToAddList::default(),
             crates_list: CratesList::default(),
@@ -176,382 +372,867 @@ impl App {
             loader_state: ThrobberState::default(),
             exit: false,
             is_showing_features: false,
+            is_showing_versions: false,
+            versions: Versions::default(),
+            focus: Focus::default(),
+            packages: None,
+            is_showing_packages: false,
+            presets: Presets::default(),
+            is_showing_presets: false,
+            save_profile: None,
+            is_showing_preview: false,
+            preview: Preview::default(),
+            catalog_status,
             is_adding_dependencies: false,
             popup_widget: Popup::default(),
+            add_results: None,
+            readme: None,
+            export: None,
+            crates_io: CratesIoClient::new()?,
+            index: IndexClient::new(CRATES_IO_INDEX_URL),
+            registries: Registries::detect(),
             features: Features::default(),
-            general_crates,
-            math_crates,
-            ffi_crates,
-            cryptography_crates,
-            common_crates,
-            concurrency_crates,
-            networking_crates,
-            database_crates,
-            clis_crates,
-            graphics_crates,
-        }
-    }
+            categories: Vec::new(),
+            existing_dependencies: ExistingDependencies::detect(),
+            search: None,
+            is_typing_search: Arc::new(AtomicBool::new(false)),
+            is_typing_profile_name: Arc::new(AtomicBool::new(false)),
+        };
 
-    pub fn set_adding_deps_operation_message(&mut self, message: &str) {
-        self.popup_widget.message = message.to_string();
-    }
+        app.load_catalog(parser);
 
-    pub fn exit(&mut self) {
-        self.exit = true;
+        Ok(app)
     }
 
-    pub fn next_category(&mut self) {
-        self.crates_list.state.select(Some(0));
-        self.crate_categories.widget = self.crate_categories.widget.next();
+    ///Replaces the crates of every category, keeping selected the crates the user already chose
+    pub fn load_catalog(&mut self, parser: &dyn ContentParser) {
+        let highlighted = self.get_current_crate_selected().map(|krate| krate.name);
+
+        self.categories = parser
+            .get_categories()
+            .into_iter()
+            .map(|category| CategoryCrates {
+                name: category.name,
+                crates: category.table.into(),
+                collapsed_sections: BTreeSet::new(),
+            })
+            .collect();
+
+        for category in &mut self.categories {
+            for krate in &mut category.crates {
+                krate.kind =
+                    DependencyKind::suggested(&category.name, &krate.use_case, &krate.name);
+                krate.existing = self.existing_dependencies.get(&krate.name).cloned();
+
+                // It stays selected unless the user wants to remove it
+                if let Some(existing) = &krate.existing {
+                    krate.kind = existing.kind;
+                    krate.default_features = existing.default_features;
+                    krate.status = ItemListStatus::Selected;
+                    if krate.registry.is_none() {
+                        krate.registry.clone_from(&existing.registry);
+                    }
+                }
+            }
 
-        self.crate_categories
-            .state
-            .select(Some(self.crate_categories.widget as usize));
-    }
+            select_crates_to_add(&mut category.crates, &self.crates_to_add.widget.crates);
+        }
 
-    pub fn previos_category(&mut self) {
-        self.crates_list.state.select(Some(0));
-        self.crate_categories.widget = self.crate_categories.widget.previous();
+        self.crate_categories.widget = CategoriesWidget::new(
+            self.categories
+                .iter()
+                .map(|category| category.name.clone())
+                .collect(),
+        );
 
-        self.crate_categories
-            .state
-            .select(Some(self.crate_categories.widget as usize));
-    }
+        if self.current_category_index() >= self.categories.len() {
+            self.crate_categories.state.select(Some(0));
+        }
 
-    pub fn render_categories_list(&mut self, area: Rect, buf: &mut Buffer) {
-        let block_tabs = Block::bordered()
-            .title_top("Categories")
-            .border_set(border::ROUNDED);
+        // The refresh arrives in the background, the cursor stays on the crate the user was on
+        let row = highlighted.and_then(|name| self.row_of_crate(&name));
 
-        block_tabs.render(area, buf);
+        if row.is_none() {
+            self.is_showing_features = false;
+            self.is_showing_versions = false;
+        }
 
-        let margin = area.inner(&Margin {
-            horizontal: 1,
-            vertical: 3,
-        });
+        self.crates_list.state.select(Some(row.unwrap_or(0)));
+    }
 
-        StatefulWidget::render(
-            self.crate_categories.widget,
-            margin,
-            buf,
-            &mut self.crate_categories.state,
-        );
+    fn row_of_crate(&self, crate_name: &str) -> Option<usize> {
+        self.current_rows().iter().position(|row| {
+            let (category, index) = match row {
+                CrateListRow::Crate(index) => (self.current_category_index(), *index),
+                CrateListRow::Found { category, index } => (*category, *index),
+                CrateListRow::Section(_) => return false,
+            };
+
+            self.categories
+                .get(category)
+                .and_then(|category| category.crates.get(index))
+                .is_some_and(|krate| krate.name == crate_name)
+        })
     }
 
-    pub fn render_main_section(&mut self, area: Rect, buf: &mut Buffer) {
-        let instructions = Title::from(Line::from(vec![
-            "Move down ".into(),
-            "<Down> <j> ".bold().blue(),
-            "Move up ".into(),
-            "<Up> <k> ".bold().blue(),
-            "Check docs ".into(),
-            "<d> ".blue(),
-            "Check crates.io ".into(),
-            "<c> ".blue(),
-            "Select features ".into(),
-            "<f> ".blue(),
-        ]));
+    #[inline]
+    pub fn current_category_index(&self) -> usize {
+        self.crate_categories.state.selected().unwrap_or(0)
+    }
 
-        Block::bordered()
-            .title("Crate name, description")
-            .border_set(border::ROUNDED)
-            .title(
-                instructions
-                    .position(Position::Bottom)
-                    .alignment(Alignment::Center),
-            )
-            .render(area, buf);
+    ///The crates of the category being displayed
+    pub fn current_crates(&self) -> &[CrateItemList] {
+        self.categories
+            .get(self.current_category_index())
+            .map_or(&[], |category| category.crates.as_slice())
+    }
 
-        let inner_area_for_list = area.inner(&Margin {
-            vertical: 1,
-            horizontal: 1,
-        });
+    pub fn current_rows(&self) -> Vec<CrateListRow> {
+        if let Some(query) = &self.search {
+            return self.search_rows(query);
+        }
 
-        self.render_crates_list(inner_area_for_list, buf);
+        self.categories
+            .get(self.current_category_index())
+            .map(CategoryCrates::rows)
+            .unwrap_or_default()
     }
 
-    fn render_crates_list(&mut self, area: Rect, buf: &mut Buffer) {
-        match self.crate_categories.widget {
-            CategoriesWidget::General => {
-                self.crates_list.widget = CratesListWidget::new(&self.general_crates);
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Graphics => {
-                self.crates_list.widget = CratesListWidget::new(&self.graphics_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Concurrency => {
-                self.crates_list.widget = CratesListWidget::new(&self.concurrency_crates);
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Clis => {
-                self.crates_list.widget = CratesListWidget::new(&self.clis_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::FFI => {
-                self.crates_list.widget = CratesListWidget::new(&self.ffi_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Math => {
-                self.crates_list.widget = CratesListWidget::new(&self.math_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
+    ///The crates of every category matching the query, best matches first. A crate listed in
+    ///more than one category is found once
+    fn search_rows(&self, query: &str) -> Vec<CrateListRow> {
+        let mut found: Vec<(u32, &str, CrateListRow)> = Vec::new();
 
-            CategoriesWidget::Common => {
-                self.crates_list.widget = CratesListWidget::new(&self.common_crates);
+        for (category, category_crates) in self.categories.iter().enumerate() {
+            for (index, krate) in category_crates.crates.iter().enumerate() {
+                if found.iter().any(|(_, name, _)| *name == krate.name) {
+                    continue;
+                }
 
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Databases => {
-                self.crates_list.widget = CratesListWidget::new(&self.database_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Networking => {
-                self.crates_list.widget = CratesListWidget::new(&self.networking_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
-            }
-            CategoriesWidget::Cryptography => {
-                self.crates_list.widget = CratesListWidget::new(&self.cryptography_crates);
-
-                StatefulWidgetRef::render_ref(
-                    &self.crates_list.widget,
-                    area,
-                    buf,
-                    &mut self.crates_list.state,
-                );
+                if let Some(score) = crate_search_score(query, krate) {
+                    found.push((score, &krate.name, CrateListRow::Found { category, index }));
+                }
             }
-        };
+        }
+
+        // Stable, so equally good matches keep the order of the categories
+        found.sort_by_key(|(score, ..)| Reverse(*score));
+
+        found.into_iter().map(|(_, _, row)| row).collect()
     }
 
-    fn render_crates_to_add_list(&mut self, area: Rect, buf: &mut Buffer) {
-        self.crates_to_add.widget =
-            CratesToAddListWidget::new(self.crates_to_add.widget.crates.clone());
-        StatefulWidgetRef::render_ref(
-            &self.crates_to_add.widget,
-            area,
-            buf,
-            &mut self.crates_to_add.state,
-        );
+    #[inline]
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
     }
 
-    fn render_footer_instructions(area: Rect, buf: &mut Buffer) {
-        FooterInstructions::new(vec![
-            " Next category ".into(),
-            "<Tab>".blue(),
-            " Previous category ".into(),
-            "<Shift + Tab>".blue(),
-            " Toggle select ".into(),
-            "<s>".blue(),
-            " Toggle select all ".into(),
-            "<a>".blue(),
-            " Add selected dependencies ".into(),
-            "<Enter>".bold().blue(),
-            " Quit ".into(),
-            " <q> <Esc> ".bold().blue(),
-        ])
-        .render(area, buf);
+    #[inline]
+    pub fn is_typing_search(&self) -> bool {
+        self.is_typing_search.load(Ordering::Relaxed)
     }
 
-    pub fn scroll_down(&mut self) {
-        self.crates_list.state.next();
+    ///For the task reading the keyboard
+    pub fn typing_search_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.is_typing_search)
     }
 
-    pub fn scroll_up(&mut self) {
-        self.crates_list.state.previous();
+    ///For the task reading the keyboard
+    pub fn typing_profile_name_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.is_typing_profile_name)
     }
 
-    pub fn scroll_up_features(&mut self) {
-        let next_index = match self.features.state.selected() {
-            Some(index) => {
-                let (current_crate_selected, _) = self.get_current_crate_selected().unwrap();
-                if index == 0 {
-                    current_crate_selected
-                        .features
-                        .unwrap_or_default()
-                        .len()
-                        .saturating_sub(1)
-                } else {
-                    index.saturating_sub(1)
-                }
-            }
-            None => 1,
-        };
-        self.features.state.select(Some(next_index));
+    ///Starts typing a new search or goes back to editing the current one
+    pub fn start_search(&mut self) {
+        if self.search.is_none() {
+            self.search = Some(String::new());
+            self.crates_list.state.select(Some(0));
+        }
+        self.is_typing_search.store(true, Ordering::Relaxed);
     }
 
-    pub fn scroll_down_features(&mut self) {
-        let next = match self.features.state.selected() {
-            Some(index) => {
-                let (current_crate_selected, _) = self.get_current_crate_selected().unwrap();
-                if index
-                    == current_crate_selected
-                        .features
-                        .unwrap_or_default()
-                        .len()
-                        .saturating_sub(1)
-                {
-                    0
-                } else {
-                    index.saturating_add(1)
-                }
-            }
-            None => self.features.state.selected().unwrap_or(0),
-        };
+    pub fn type_search(&mut self, character: char) {
+        if let Some(query) = self.search.as_mut() {
+            query.push(character);
+            self.crates_list.state.select(Some(0));
+        }
+    }
 
-        self.features.state.select(Some(next));
+    pub fn delete_search_character(&mut self) {
+        if let Some(query) = self.search.as_mut() {
+            query.pop();
+            self.crates_list.state.select(Some(0));
+        }
     }
 
-    pub fn toggle_select_all_dependencies(&mut self) {
-        match self.crate_categories.widget {
-            CategoriesWidget::Clis => {
-                toggle_status_all(&mut self.clis_crates);
-            }
-            CategoriesWidget::Graphics => {
-                toggle_status_all(&mut self.graphics_crates);
-            }
-            CategoriesWidget::Concurrency => {
-                toggle_status_all(&mut self.concurrency_crates);
-            }
+    ///Keeps the results so the shortcuts work on them again
+    pub fn confirm_search(&mut self) {
+        self.is_typing_search.store(false, Ordering::Relaxed);
+    }
 
-            CategoriesWidget::FFI => {
-                toggle_status_all(&mut self.ffi_crates);
-            }
-            CategoriesWidget::Math => {
-                toggle_status_all(&mut self.math_crates);
-            }
+    pub fn close_search(&mut self) {
+        self.search = None;
+        self.is_typing_search.store(false, Ordering::Relaxed);
+        self.crates_list.state.select(Some(0));
+    }
 
-            CategoriesWidget::Common => {
-                toggle_status_all(&mut self.common_crates);
-            }
+    ///The crates currently listed, the ones of the category or the search results
+    fn listed_crates(&self) -> Vec<CrateItemList> {
+        if self.search.is_none() {
+            return self.current_crates().to_vec();
+        }
+
+        self.current_rows()
+            .into_iter()
+            .filter_map(|row| match row {
+                CrateListRow::Found { category, index } => {
+                    self.categories[category].crates.get(index).cloned()
+                }
+                CrateListRow::Section(_) | CrateListRow::Crate(_) => None,
+            })
+            .collect()
+    }
+
+    ///The names of the categories listing a crate, shown next to the search results
+    fn categories_of(&self, crate_name: &str) -> Vec<&str> {
+        self.categories
+            .iter()
+            .filter(|category| category.crates.iter().any(|krate| krate.name == crate_name))
+            .map(|category| category.name.as_str())
+            .collect()
+    }
 
-            CategoriesWidget::General => {
-                toggle_status_all(&mut self.general_crates);
+    ///A crate can be listed in more than one category, every copy gets what the user chose in the
+    ///one they changed
+    fn sync_crate(&mut self, changed: &CrateItemList) {
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == changed.name)
+            {
+                krate.status = changed.status.clone();
+                krate.features.clone_from(&changed.features);
+                krate.default_features = changed.default_features;
+                krate.version.clone_from(&changed.version);
+                krate.kind = changed.kind;
             }
+        }
+    }
+
+    pub fn current_crates_mut(&mut self) -> &mut [CrateItemList] {
+        let index = self.current_category_index();
+        self.categories
+            .get_mut(index)
+            .map_or(&mut [], |category| category.crates.as_mut_slice())
+    }
 
-            CategoriesWidget::Databases => {
-                toggle_status_all(&mut self.database_crates);
+    ///Sets the features of every crate with this name, a crate can be listed in more than one
+    ///category
+    pub fn load_features(&mut self, crate_name: &str, features: Option<&[FeatureItemList]>) {
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == crate_name)
+            {
+                load_features(
+                    krate,
+                    features.map(<[FeatureItemList]>::to_vec),
+                    &self.crates_to_add.widget.crates,
+                );
             }
+        }
+    }
 
-            CategoriesWidget::Networking => {
-                toggle_status_all(&mut self.networking_crates);
+    pub fn load_details(&mut self, crate_name: &str, details: &CrateDetails) {
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == crate_name)
+            {
+                krate.details = Some(details.clone());
             }
+        }
+    }
 
-            CategoriesWidget::Cryptography => {
-                toggle_status_all(&mut self.cryptography_crates);
+    pub fn load_versions(&mut self, crate_name: &str, versions: Option<&[VersionItemList]>) {
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == crate_name)
+            {
+                krate.versions = versions.map(<[VersionItemList]>::to_vec);
             }
         }
     }
 
-    pub fn get_current_crate_selected(&self) -> Option<(CrateItemList, usize)> {
-        self.crates_list.state.selected.map(|index| {
-            let crate_item = self.crates_list.widget.crates[index].clone();
-            (crate_item, index)
-        })
+    pub fn set_catalog_offline(&mut self) {
+        if let CatalogStatus::Refreshing(fetched_at) = self.catalog_status {
+            self.catalog_status = CatalogStatus::Offline(fetched_at);
+        }
     }
 
-    pub fn toggle_select_dependencie(&mut self) {
-        if let Some(index_crate_selected) = self.crates_list.state.selected {
-            match self.crate_categories.widget {
-                CategoriesWidget::Clis => {
-                    toggle_status_one_crate(&mut self.clis_crates[index_crate_selected]);
-                }
-                CategoriesWidget::Graphics => {
-                    toggle_status_one_crate(&mut self.graphics_crates[index_crate_selected]);
-                }
-                CategoriesWidget::Concurrency => {
-                    toggle_status_one_crate(&mut self.concurrency_crates[index_crate_selected]);
-                }
+    pub fn show_add_report(&mut self, report: AddReport) {
+        self.is_adding_dependencies = false;
 
-                CategoriesWidget::Cryptography => {
-                    toggle_status_one_crate(&mut self.cryptography_crates[index_crate_selected]);
-                }
+        let can_restore = report.has_failures() && report.backup.is_some();
 
-                CategoriesWidget::Networking => {
-                    toggle_status_one_crate(&mut self.networking_crates[index_crate_selected]);
-                }
+        self.add_results = Some(AddResults {
+            widget: AddResultsWidget {
+                results: report.results,
+                can_restore,
+                restore_message: None,
+            },
+            backup: report.backup,
+            scroll: 0,
+        });
+    }
 
-                CategoriesWidget::Databases => {
-                    toggle_status_one_crate(&mut self.database_crates[index_crate_selected]);
-                }
+    #[inline]
+    pub fn is_showing_add_results(&self) -> bool {
+        self.add_results.is_some()
+    }
 
-                CategoriesWidget::General => {
-                    toggle_status_one_crate(&mut self.general_crates[index_crate_selected]);
-                }
+    ///Puts back Cargo.toml and Cargo.lock as they were before adding the crates, only once
+    pub fn restore_manifest(&mut self) {
+        let Some(add_results) = self.add_results.as_mut() else {
+            return;
+        };
 
-                CategoriesWidget::Common => {
-                    toggle_status_one_crate(&mut self.common_crates[index_crate_selected]);
-                }
+        if !add_results.widget.can_restore {
+            return;
+        }
 
-                CategoriesWidget::Math => {
-                    toggle_status_one_crate(&mut self.math_crates[index_crate_selected]);
-                }
+        if let Some(backup) = &add_results.backup {
+            add_results.widget.restore_message = Some(match backup.restore() {
+                Ok(()) => "Cargo.toml was restored, no crate was added".to_string(),
+                Err(e) => format!("Could not restore Cargo.toml: {e}"),
+            });
+            add_results.widget.can_restore = false;
+            add_results.scroll = 0;
+        }
+    }
 
-                CategoriesWidget::FFI => {
-                    toggle_status_one_crate(&mut self.ffi_crates[index_crate_selected]);
-                }
+    pub fn scroll_down_add_results(&mut self) {
+        if let Some(add_results) = self.add_results.as_mut() {
+            let lines_count = u16::try_from(add_results.widget.lines_count()).unwrap_or(u16::MAX);
+            if add_results.scroll + 1 < lines_count {
+                add_results.scroll += 1;
+            }
+        }
+    }
+
+    pub fn scroll_up_add_results(&mut self) {
+        if let Some(add_results) = self.add_results.as_mut() {
+            add_results.scroll = add_results.scroll.saturating_sub(1);
+        }
+    }
+
+    ///Opens the README of the version chosen for the highlighted crate, the latest one otherwise.
+    ///Returns the crate and version to download it from, `None` if the version is not known yet
+    pub fn show_readme(&mut self) -> Option<(String, String)> {
+        let current_crate = self.get_current_crate_selected()?;
+
+        let version = current_crate
+            .version
+            .as_deref()
+            .map(requirement_version)
+            .map(ToString::to_string)
+            .or(current_crate.details.map(|details| details.latest_version));
+
+        let (version, content) = match (&current_crate.registry, version) {
+            (Some(registry), _) => (
+                String::new(),
+                ReadmeContent::Failed(format!(
+                    "READMEs come from crates.io, {} is published to the {registry} registry",
+                    current_crate.name
+                )),
+            ),
+            (None, Some(version)) => (version, ReadmeContent::Loading),
+            (None, None) => (
+                String::new(),
+                ReadmeContent::Failed(
+                    "The crate is still being fetched from crates.io, try again in a moment"
+                        .to_string(),
+                ),
+            ),
+        };
+
+        let is_loading = matches!(content, ReadmeContent::Loading);
+
+        self.readme = Some(Readme {
+            widget: ReadmeWidget {
+                crate_name: current_crate.name.clone(),
+                version: version.clone(),
+                content,
+            },
+            scroll: 0,
+        });
+
+        is_loading.then_some((current_crate.name, version))
+    }
+
+    ///The README arrives after the user may have closed it or opened the one of another crate
+    pub fn load_readme(&mut self, crate_name: &str, readme: Result<String, String>) {
+        if let Some(shown) = self
+            .readme
+            .as_mut()
+            .filter(|shown| shown.widget.crate_name == crate_name)
+        {
+            shown.widget.content = match readme {
+                Ok(markdown) => ReadmeContent::Loaded(markdown_lines(&markdown)),
+                Err(error) => ReadmeContent::Failed(format!("Could not get the README: {error}")),
             };
         }
     }
 
+    #[inline]
+    pub fn is_showing_readme(&self) -> bool {
+        self.readme.is_some()
+    }
+
+    pub fn close_readme(&mut self) {
+        self.readme = None;
+    }
+
+    ///Rendering the README keeps it from scrolling past its last line
+    pub fn scroll_down_readme(&mut self, lines: u16) {
+        if let Some(readme) = self.readme.as_mut() {
+            readme.scroll = readme.scroll.saturating_add(lines);
+        }
+    }
+
+    pub fn scroll_up_readme(&mut self, lines: u16) {
+        if let Some(readme) = self.readme.as_mut() {
+            readme.scroll = readme.scroll.saturating_sub(lines);
+        }
+    }
+
+    ///The export is rendered once the latest versions are known. Returns the ones the catalog
+    ///already knows, the others are looked up in the index
+    pub fn show_export(&mut self) -> BTreeMap<String, String> {
+        self.export = Some(Export {
+            widget: ExportWidget {
+                format: ExportFormat::default(),
+                contents: None,
+                status: None,
+            },
+            latest_versions: None,
+            lookup_errors: Vec::new(),
+            scroll: 0,
+        });
+
+        let mut latest_versions = BTreeMap::new();
+
+        for krate in self.categories.iter().flat_map(|category| &category.crates) {
+            if let Some(latest) = krate
+                .versions
+                .iter()
+                .flatten()
+                .find(|version| !version.yanked && !version.num.contains('-'))
+            {
+                latest_versions.insert(krate.name.clone(), latest.num.clone());
+            }
+        }
+
+        latest_versions
+    }
+
+    ///The crates without a version requirement are exported with their latest version
+    pub fn load_export_versions(
+        &mut self,
+        latest_versions: BTreeMap<String, String>,
+        lookup_errors: Vec<String>,
+    ) {
+        let crates = &self.crates_to_add.widget.crates;
+
+        if let Some(export) = self.export.as_mut() {
+            export.widget.contents = Some(export::render(
+                crates,
+                export.widget.format,
+                &latest_versions,
+            ));
+            export.widget.status = lookup_status(&lookup_errors);
+            export.latest_versions = Some(latest_versions);
+            export.lookup_errors = lookup_errors;
+        }
+    }
+
+    #[inline]
+    pub fn is_showing_export(&self) -> bool {
+        self.export.is_some()
+    }
+
+    pub fn close_export(&mut self) {
+        self.export = None;
+    }
+
+    pub fn cycle_export_format(&mut self) {
+        let crates = &self.crates_to_add.widget.crates;
+
+        let Some(export) = self.export.as_mut() else {
+            return;
+        };
+
+        let Some(latest_versions) = &export.latest_versions else {
+            return;
+        };
+
+        let format = export.widget.format.next();
+
+        export.widget = ExportWidget {
+            format,
+            contents: Some(export::render(crates, format, latest_versions)),
+            status: lookup_status(&export.lookup_errors),
+        };
+        export.scroll = 0;
+    }
+
+    pub fn copy_export(&mut self) {
+        if let Some(export) = self.export.as_mut() {
+            let Some(Ok(contents)) = &export.widget.contents else {
+                return;
+            };
+
+            export.widget.status = Some(
+                export::copy_to_clipboard(contents)
+                    .map(|()| "Copied to the clipboard".to_string())
+                    .map_err(|e| format!("Could not copy to the clipboard: {e}")),
+            );
+        }
+    }
+
+    ///Writes the export to the current directory, next to the project's Cargo.toml
+    pub fn write_export(&mut self) {
+        if let Some(export) = self.export.as_mut() {
+            let Some(Ok(contents)) = &export.widget.contents else {
+                return;
+            };
+
+            let format = export.widget.format;
+            let path = env::current_dir()
+                .unwrap_or_default()
+                .join(format.file_name());
+
+            export.widget.status = Some(
+                export::write_export(&path, contents, format)
+                    .map(|()| format!("Written to {}", path.display()))
+                    .map_err(|e| format!("Could not write {}: {e}", path.display())),
+            );
+        }
+    }
+
+    pub fn scroll_down_export(&mut self) {
+        if let Some(export) = self.export.as_mut() {
+            export.scroll = export.scroll.saturating_add(1);
+        }
+    }
+
+    pub fn scroll_up_export(&mut self) {
+        if let Some(export) = self.export.as_mut() {
+            export.scroll = export.scroll.saturating_sub(1);
+        }
+    }
+
+    pub fn exit(&mut self) {
+        self.exit = true;
+    }
+
+    pub fn next_category(&mut self) {
+        let total_categories = self.categories.len();
+        if total_categories == 0 {
+            return;
+        }
+
+        self.close_search();
+
+        self.crates_list.state.select(Some(0));
+        self.crate_categories
+            .state
+            .select(Some((self.current_category_index() + 1) % total_categories));
+    }
+
+    pub fn previos_category(&mut self) {
+        let total_categories = self.categories.len();
+        if total_categories == 0 {
+            return;
+        }
+
+        self.close_search();
+
+        self.crates_list.state.select(Some(0));
+        self.crate_categories.state.select(Some(
+            (self.current_category_index() + total_categories - 1) % total_categories,
+        ));
+    }
+
+    pub fn render_categories_list(&mut self, area: Rect, buf: &mut Buffer) {
+        let block_tabs = Block::bordered()
+            .title_top("Categories")
+            .border_set(border::ROUNDED);
+
+        block_tabs.render(area, buf);
+
+        let margin = area.inner(&Margin {
+            horizontal: 1,
+            vertical: 3,
+        });
+
+        StatefulWidgetRef::render_ref(
+            &self.crate_categories.widget,
+            margin,
+            buf,
+            &mut self.crate_categories.state,
+        );
+    }
+
+    pub fn render_main_section(&mut self, area: Rect, buf: &mut Buffer) {
+        let instructions = Title::from(Line::from(vec![
+            "Move down ".into(),
+            "<Down> <j> ".bold().blue(),
+            "Move up ".into(),
+            "<Up> <k> ".bold().blue(),
+            "Check docs ".into(),
+            "<d> ".blue(),
+            "Check crates.io ".into(),
+            "<c> ".blue(),
+            "README ".into(),
+            "<i> ".blue(),
+            "Select features ".into(),
+            "<f> ".blue(),
+            "Fold section ".into(),
+            "<Space> ".blue(),
+        ]));
+
+        let title = match &self.search {
+            Some(query) => {
+                let cursor = if self.is_typing_search() { "_" } else { "" };
+                Line::from(vec![
+                    "Search ".into(),
+                    format!("/{query}{cursor}").bold().yellow(),
+                    format!(" {} found, every category ", self.current_rows().len()).dim(),
+                    "<Esc>".bold().blue(),
+                    " to close".dim(),
+                ])
+            }
+            None => Line::from("Crate name, description"),
+        };
+
+        Block::bordered()
+            .title(title)
+            .border_set(border::ROUNDED)
+            .title(
+                instructions
+                    .position(Position::Bottom)
+                    .alignment(Alignment::Center),
+            )
+            .render(area, buf);
+
+        let inner_area_for_list = area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+
+        self.render_crates_list(inner_area_for_list, buf);
+    }
+
+    fn render_crates_list(&mut self, area: Rect, buf: &mut Buffer) {
+        let items = match self.categories.get(self.current_category_index()) {
+            Some(_) if self.is_searching() => self
+                .listed_crates()
+                .into_iter()
+                .map(|mut krate| {
+                    krate.found_in = Some(self.categories_of(&krate.name).join(", "));
+                    CrateListItem::Crate(Box::new(krate))
+                })
+                .collect(),
+            Some(category) => category
+                .rows()
+                .into_iter()
+                .map(|row| match row {
+                    CrateListRow::Section(index) => {
+                        let use_case = &category.crates[index].use_case;
+                        CrateListItem::section(
+                            use_case.clone(),
+                            category.section_len(use_case),
+                            category.collapsed_sections.contains(use_case),
+                        )
+                    }
+                    CrateListRow::Crate(index) | CrateListRow::Found { index, .. } => {
+                        CrateListItem::Crate(Box::new(category.crates[index].clone()))
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        self.crates_list.widget = CratesListWidget::new(items);
+
+        StatefulWidgetRef::render_ref(
+            &self.crates_list.widget,
+            area,
+            buf,
+            &mut self.crates_list.state,
+        );
+    }
+
+    fn render_crates_to_add_list(&mut self, area: Rect, buf: &mut Buffer) {
+        self.crates_to_add.widget = CratesToAddListWidget::new(
+            self.crates_to_add.widget.crates.clone(),
+            self.focus == Focus::CratesToAdd,
+        );
+        StatefulWidgetRef::render_ref(
+            &self.crates_to_add.widget,
+            area,
+            buf,
+            &mut self.crates_to_add.state,
+        );
+    }
+
+    fn render_footer_instructions(&self, area: Rect, buf: &mut Buffer) {
+        let mut instructions = vec![
+            " Next category ".into(),
+            "<Tab>".blue(),
+            " Previous category ".into(),
+            "<Shift + Tab>".blue(),
+            " Toggle select ".into(),
+            "<s>".blue(),
+            " Toggle select all ".into(),
+            "<a>".blue(),
+            " Search ".into(),
+            "</>".blue(),
+            " Presets ".into(),
+            "<p>".blue(),
+        ];
+
+        if self.is_workspace() {
+            instructions.extend([" Packages ".into(), "<w>".blue()]);
+        }
+
+        instructions.extend([
+            " Review and add selected dependencies ".into(),
+            "<Enter>".bold().blue(),
+            " Quit ".into(),
+            " <q> <Esc> ".bold().blue(),
+        ]);
+
+        FooterInstructions::new(instructions, self.catalog_status).render(area, buf);
+    }
+
+    pub fn scroll_down(&mut self) {
+        self.crates_list.state.next();
+    }
+
+    pub fn scroll_up(&mut self) {
+        self.crates_list.state.previous();
+    }
+
+    pub fn scroll_up_features(&mut self) {
+        let total = self.current_features_len();
+        scroll_wrapping(&mut self.features.state, total, ScrollDirection::Up);
+    }
+
+    pub fn scroll_down_features(&mut self) {
+        let total = self.current_features_len();
+        scroll_wrapping(&mut self.features.state, total, ScrollDirection::Down);
+    }
+
+    fn current_features_len(&self) -> usize {
+        self.get_current_crate_selected()
+            .and_then(|krate| krate.features)
+            .map_or(0, |features| features.len())
+    }
+
+    ///While searching it is every crate found
+    pub fn toggle_select_all_dependencies(&mut self) {
+        if self.search.is_none() {
+            toggle_status_all(self.current_crates_mut());
+            return;
+        }
+
+        let mut found = self.listed_crates();
+        toggle_status_all(&mut found);
+
+        for krate in &found {
+            self.sync_crate(krate);
+        }
+    }
+
+    ///The category and index of the crate under the cursor, there is none when the cursor is on
+    ///the header of a section
+    fn current_crate_position(&self) -> Option<(usize, usize)> {
+        let row = self.crates_list.state.selected?;
+
+        match self.current_rows().get(row)? {
+            CrateListRow::Crate(index) => Some((self.current_category_index(), *index)),
+            CrateListRow::Found { category, index } => Some((*category, *index)),
+            CrateListRow::Section(_) => None,
+        }
+    }
+
+    pub fn get_current_crate_selected(&self) -> Option<CrateItemList> {
+        let (category, index) = self.current_crate_position()?;
+        self.categories.get(category)?.crates.get(index).cloned()
+    }
+
+    pub fn current_crate_mut(&mut self) -> Option<&mut CrateItemList> {
+        let (category, index) = self.current_crate_position()?;
+        self.categories.get_mut(category)?.crates.get_mut(index)
+    }
+
+    pub fn toggle_select_dependencie(&mut self) {
+        if let Some(current_crate) = self.current_crate_mut() {
+            toggle_status_one_crate(current_crate);
+        }
+    }
+
+    ///Collapses or expands the section the cursor is in, leaving the cursor on its header
+    pub fn toggle_section(&mut self) {
+        // Search results are not grouped
+        let Some(row) = self
+            .crates_list
+            .state
+            .selected
+            .filter(|_| !self.is_searching())
+        else {
+            return;
+        };
+
+        let index = self.current_category_index();
+        let Some(category) = self.categories.get_mut(index) else {
+            return;
+        };
+
+        let index_crate = match category.rows().get(row) {
+            Some(CrateListRow::Section(index) | CrateListRow::Crate(index)) => *index,
+            Some(CrateListRow::Found { .. }) | None => return,
+        };
+
+        let use_case = category.crates[index_crate].use_case.clone();
+
+        if !category.collapsed_sections.remove(&use_case) {
+            category.collapsed_sections.insert(use_case.clone());
+        }
+
+        let header_row = category.rows().iter().position(|row| {
+            matches!(row, CrateListRow::Section(index) if category.crates[*index].use_case == use_case)
+        });
+
+        if header_row.is_some() {
+            self.crates_list.state.select(header_row);
+        }
+    }
+
     #[inline]
     pub fn show_popup(&mut self) {
         self.is_adding_dependencies = true;
     }
 
     pub fn check_docs(&self) {
-        if let Some(index_selected) = self.crates_list.state.selected {
-            let crate_name = &self.crates_list.widget.crates[index_selected].name;
+        if let Some(crate_selected) = self.get_current_crate_selected() {
+            let crate_name = &crate_selected.name;
             let url = format!("https://docs.rs/{crate_name}/latest/{crate_name}/");
 
             open::that(url).ok();
@@ -559,11 +1240,8 @@ impl App {
     }
 
     pub fn check_crates_io(&self) {
-        if let Some(index_selected) = self.crates_list.state.selected {
-            let url = format!(
-                "https://crates.io/crates/{}",
-                self.crates_list.widget.crates[index_selected].name
-            );
+        if let Some(crate_selected) = self.get_current_crate_selected() {
+            let url = format!("https://crates.io/crates/{}", crate_selected.name);
             open::that(url).ok();
         }
     }
@@ -574,53 +1252,15 @@ impl App {
     }
 
     ///This method checks for selected crates, adds them and ensures not selected crates are
-    ///removed
+    ///removed. The crates listed are the ones the user could have changed
     pub fn push_or_remove_selected_crates(&mut self) {
-        match self.crate_categories.widget {
-            CategoriesWidget::FFI => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.ffi_crates);
-            }
-            CategoriesWidget::Math => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.math_crates);
-            }
-            CategoriesWidget::Clis => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.clis_crates);
-            }
-
-            CategoriesWidget::Common => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.common_crates);
-            }
+        let listed = self.listed_crates();
 
-            CategoriesWidget::General => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.general_crates);
-            }
-            CategoriesWidget::Graphics => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.graphics_crates);
-            }
-            CategoriesWidget::Databases => {
-                push_or_remove_crates(&mut self.crates_to_add.widget.crates, &self.database_crates);
-            }
-
-            CategoriesWidget::Networking => {
-                push_or_remove_crates(
-                    &mut self.crates_to_add.widget.crates,
-                    &self.networking_crates,
-                );
-            }
-
-            CategoriesWidget::Concurrency => {
-                push_or_remove_crates(
-                    &mut self.crates_to_add.widget.crates,
-                    &self.concurrency_crates,
-                );
-            }
-            CategoriesWidget::Cryptography => {
-                push_or_remove_crates(
-                    &mut self.crates_to_add.widget.crates,
-                    &self.cryptography_crates,
-                );
-            }
+        for krate in &listed {
+            self.sync_crate(krate);
         }
+
+        push_or_remove_crates(&mut self.crates_to_add.widget.crates, &listed);
     }
 
     #[inline]
@@ -632,69 +1272,644 @@ impl App {
     }
 
     pub fn toggle_select_one_feature(&mut self) {
-        let (current_crate_selected, index_current_crate_selected) =
-            self.get_current_crate_selected().unwrap();
-        if !current_crate_selected.is_loading {
-            match self.crate_categories.widget {
-                CategoriesWidget::General => {
-                    let current_crate = &mut self.general_crates[index_current_crate_selected];
-
-                    toggle_one_feature(current_crate, &self.features.state);
-                }
-                CategoriesWidget::Common => {
-                    let current_crate = &mut self.common_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
-                }
-                CategoriesWidget::FFI => {
-                    let current_crate = &mut self.ffi_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
-                }
+        let features_state = self.features.state.clone();
+        if let Some(current_crate) = self
+            .current_crate_mut()
+            .filter(|current_crate| !current_crate.is_loading)
+        {
+            toggle_one_feature(current_crate, &features_state);
+        }
+    }
 
-                CategoriesWidget::Math => {
-                    let current_crate = &mut self.math_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
-                }
+    #[inline]
+    pub fn is_focusing_crates_to_add(&self) -> bool {
+        self.focus == Focus::CratesToAdd
+    }
+
+    pub fn focus_crates_to_add(&mut self) {
+        let total = self.crates_to_add.widget.crates.len();
+        if total == 0 {
+            return;
+        }
+
+        self.focus = Focus::CratesToAdd;
 
-                CategoriesWidget::Clis => {
-                    let current_crate = &mut self.clis_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+        if self
+            .crates_to_add
+            .state
+            .selected()
+            .is_none_or(|index| index >= total)
+        {
+            self.crates_to_add.state.select(Some(0));
+        }
+    }
+
+    pub fn focus_crates_list(&mut self) {
+        self.focus = Focus::CratesList;
+        self.crates_to_add.state.select(None);
+    }
+
+    pub fn scroll_down_crates_to_add(&mut self) {
+        let total = self.crates_to_add.widget.crates.len();
+        scroll_wrapping(&mut self.crates_to_add.state, total, ScrollDirection::Down);
+    }
+
+    pub fn scroll_up_crates_to_add(&mut self) {
+        let total = self.crates_to_add.widget.crates.len();
+        scroll_wrapping(&mut self.crates_to_add.state, total, ScrollDirection::Up);
+    }
+
+    ///Goes from `[dependencies]` to `[dev-dependencies]` to `[build-dependencies]`
+    pub fn cycle_dependency_kind(&mut self) {
+        // Crates already in Cargo.toml stay in their table
+        let Some(crate_to_add) = self
+            .crates_to_add
+            .state
+            .selected()
+            .and_then(|index| self.crates_to_add.widget.crates.get_mut(index))
+            .filter(|crate_to_add| crate_to_add.change == Change::Add)
+        else {
+            return;
+        };
+
+        crate_to_add.kind = crate_to_add.kind.next();
+
+        // Keep the crates list in sync, it is where the crates to add are rebuilt from
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == crate_to_add.crate_name)
+            {
+                krate.kind = crate_to_add.kind;
+            }
+        }
+    }
+
+    pub fn remove_highlighted_crate_to_add(&mut self) {
+        let Some(index) = self.crates_to_add.state.selected() else {
+            return;
+        };
+
+        let Some(crate_name) = self
+            .crates_to_add
+            .widget
+            .crates
+            .get(index)
+            .map(|crate_to_add| crate_to_add.crate_name.clone())
+        else {
+            return;
+        };
+
+        self.deselect_crate(&crate_name);
+
+        let total = self.crates_to_add.widget.crates.len();
+        if total == 0 {
+            self.focus_crates_list();
+        } else if index >= total {
+            self.crates_to_add.state.select(Some(total - 1));
+        }
+    }
+
+    ///Crates can only be added to the packages of a workspace after choosing them, the package of
+    ///the current directory is chosen from the start
+    pub fn set_workspace(&mut self, workspace: Option<Workspace>) {
+        self.packages = workspace
+            .filter(Workspace::has_many_packages)
+            .map(|workspace| {
+                let packages = workspace
+                    .members
+                    .iter()
+                    .map(|member| {
+                        let status = if workspace.current_package.as_ref() == Some(&member.name) {
+                            ItemListStatus::Selected
+                        } else {
+                            ItemListStatus::Unselected
+                        };
+                        (member.name.clone(), status)
+                    })
+                    .collect();
+
+                Packages {
+                    widget: PackagesWidget {
+                        packages,
+                        use_workspace_dependencies: false,
+                    },
+                    state: ListState::default(),
+                    workspace,
                 }
+            });
+    }
+
+    pub fn set_presets(&mut self, presets: Vec<Preset>, profiles: Vec<Preset>) {
+        self.presets.widget = PresetsWidget { presets, profiles };
+    }
+
+    fn total_presets(&self) -> usize {
+        self.presets.widget.presets.len() + self.presets.widget.profiles.len()
+    }
+
+    pub fn toggle_show_presets(&mut self) {
+        if self.total_presets() > 0 {
+            self.presets.state.select(Some(0));
+            self.is_showing_presets.toggle();
+        }
+    }
+
+    pub fn scroll_down_presets(&mut self) {
+        let total = self.total_presets();
+        scroll_wrapping(&mut self.presets.state, total, ScrollDirection::Down);
+    }
+
+    pub fn scroll_up_presets(&mut self) {
+        let total = self.total_presets();
+        scroll_wrapping(&mut self.presets.state, total, ScrollDirection::Up);
+    }
 
-                CategoriesWidget::Graphics => {
-                    let current_crate = &mut self.graphics_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+    ///The profiles are listed after the presets
+    pub fn apply_highlighted_preset(&mut self) {
+        let presets = &self.presets.widget;
+
+        match self.presets.state.selected() {
+            Some(index) if index < presets.presets.len() => {
+                let preset = presets.presets[index].clone();
+                self.apply_preset(&preset);
+            }
+            Some(index) => {
+                if let Some(profile) = presets.profiles.get(index - presets.presets.len()).cloned()
+                {
+                    self.restore_profile(&profile);
                 }
+            }
+            None => {}
+        }
+
+        self.is_showing_presets = false;
+    }
+
+    ///Unlike a preset the profile replaces the crates selected before
+    pub fn restore_profile(&mut self, profile: &Preset) {
+        let selected: Vec<String> = self
+            .crates_to_add
+            .widget
+            .crates
+            .iter()
+            .map(|crate_to_add| crate_to_add.crate_name.clone())
+            .collect();
+
+        for crate_name in selected {
+            self.deselect_crate(&crate_name);
+        }
+
+        self.apply_preset(profile);
+    }
+
+    pub fn start_saving_profile(&mut self) {
+        self.save_profile = Some(SaveProfileWidget::default());
+        self.is_typing_profile_name.store(true, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn is_saving_profile(&self) -> bool {
+        self.save_profile.is_some()
+    }
+
+    pub fn type_profile_name(&mut self, character: char) {
+        if let Some(save_profile) = self.save_profile.as_mut() {
+            save_profile.name.push(character);
+            save_profile.replaces_existing = false;
+        }
+    }
+
+    pub fn delete_profile_name_character(&mut self) {
+        if let Some(save_profile) = self.save_profile.as_mut() {
+            save_profile.name.pop();
+            save_profile.replaces_existing = false;
+        }
+    }
+
+    ///A saved profile is only replaced once the user confirms it. The profile saved replaces the
+    ///one with the same name in the presets popup
+    pub fn save_profile(&mut self) {
+        let Some(save_profile) = self.save_profile.as_mut() else {
+            return;
+        };
+
+        if !save_profile.replaces_existing && profile::profile_exists(&save_profile.name) {
+            save_profile.replaces_existing = true;
+            return;
+        }
+
+        self.is_typing_profile_name.store(false, Ordering::Relaxed);
 
-                CategoriesWidget::Databases => {
-                    let current_crate = &mut self.database_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+        match profile::save_profile(
+            &save_profile.name,
+            &self.crates_to_add.widget.crates,
+            save_profile.replaces_existing,
+        ) {
+            Ok((profile, path)) => {
+                save_profile.saved = Some(Ok(path.display().to_string()));
+
+                let profiles = &mut self.presets.widget.profiles;
+                profiles.retain(|saved| saved.name != profile.name);
+                profiles.push(profile);
+                profiles.sort_by(|profile, other| profile.name.cmp(&other.name));
+            }
+            Err(e) => save_profile.saved = Some(Err(e.to_string())),
+        }
+    }
+
+    pub fn close_save_profile(&mut self) {
+        self.save_profile = None;
+        self.is_typing_profile_name.store(false, Ordering::Relaxed);
+    }
+
+    ///Selects the crates of the preset in every category with its features, version and kind.
+    ///The crates the catalog does not list go straight to the crates to add
+    pub fn apply_preset(&mut self, preset: &Preset) {
+        for preset_crate in &preset.crates {
+            let mut listed: Option<CrateItemList> = None;
+
+            for krate in self
+                .categories
+                .iter_mut()
+                .flat_map(|category| category.crates.iter_mut())
+                .filter(|krate| krate.name == preset_crate.crate_name)
+            {
+                krate.status = ItemListStatus::Selected;
+                krate.default_features = preset_crate.default_features;
+                if preset_crate.version.is_some() {
+                    krate.version.clone_from(&preset_crate.version);
+                }
+                if preset_crate.registry.is_some() {
+                    krate.registry.clone_from(&preset_crate.registry);
                 }
-                CategoriesWidget::Networking => {
-                    let current_crate = &mut self.networking_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+                // A crate already in Cargo.toml stays in its table
+                if krate.existing.is_none() {
+                    krate.kind = preset_crate.kind;
                 }
-                CategoriesWidget::Concurrency => {
-                    let current_crate = &mut self.concurrency_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+                for feature in krate.features.iter_mut().flatten() {
+                    if preset_crate
+                        .features
+                        .iter()
+                        .flatten()
+                        .any(|name| *name == feature.name)
+                    {
+                        feature.status = ItemListStatus::Selected;
+                    }
                 }
+                listed = Some(krate.clone());
+            }
+
+            let selection = &mut self.crates_to_add.widget.crates;
+
+            let Some(krate) = listed else {
+                let unlisted = CrateToAdd {
+                    change: match self.existing_dependencies.get(&preset_crate.crate_name) {
+                        Some(_) => Change::Update {
+                            removed_features: Vec::new(),
+                        },
+                        None => Change::Add,
+                    },
+                    ..preset_crate.clone()
+                };
+                selection.retain(|crate_to_add| crate_to_add.crate_name != unlisted.crate_name);
+                selection.push(unlisted);
+                continue;
+            };
+
+            push_or_remove_crates(selection, std::slice::from_ref(&krate));
 
-                CategoriesWidget::Cryptography => {
-                    let current_crate = &mut self.cryptography_crates[index_current_crate_selected];
-                    toggle_one_feature(current_crate, &self.features.state);
+            // The features are selected once they are fetched
+            if krate.features.is_none() {
+                if let Some(crate_to_add) = selection
+                    .iter_mut()
+                    .find(|crate_to_add| crate_to_add.crate_name == krate.name)
+                {
+                    crate_to_add.features.clone_from(&preset_crate.features);
                 }
             }
         }
     }
 
+    #[inline]
+    pub fn is_workspace(&self) -> bool {
+        self.packages.is_some()
+    }
+
+    pub fn toggle_show_packages(&mut self) {
+        if let Some(packages) = self.packages.as_mut() {
+            packages.state.select(Some(0));
+            self.is_showing_packages.toggle();
+        }
+    }
+
+    pub fn scroll_down_packages(&mut self) {
+        if let Some(packages) = self.packages.as_mut() {
+            let total = packages.widget.packages.len();
+            scroll_wrapping(&mut packages.state, total, ScrollDirection::Down);
+        }
+    }
+
+    pub fn scroll_up_packages(&mut self) {
+        if let Some(packages) = self.packages.as_mut() {
+            let total = packages.widget.packages.len();
+            scroll_wrapping(&mut packages.state, total, ScrollDirection::Up);
+        }
+    }
+
+    pub fn toggle_select_package(&mut self) {
+        if let Some(packages) = self.packages.as_mut() {
+            if let Some((_, status)) = packages
+                .state
+                .selected()
+                .and_then(|index| packages.widget.packages.get_mut(index))
+            {
+                *status = match status {
+                    ItemListStatus::Selected => ItemListStatus::Unselected,
+                    ItemListStatus::Unselected => ItemListStatus::Selected,
+                };
+            }
+        }
+    }
+
+    pub fn toggle_workspace_dependencies(&mut self) {
+        if let Some(packages) = self.packages.as_mut() {
+            packages.widget.use_workspace_dependencies.toggle();
+        }
+    }
+
+    ///`None` when the crates go to the package of the current directory
+    pub fn workspace_target(&self) -> Option<WorkspaceTarget> {
+        self.packages.as_ref().map(|packages| WorkspaceTarget {
+            workspace: packages.workspace.clone(),
+            packages: packages
+                .widget
+                .packages
+                .iter()
+                .filter(|(_, status)| *status == ItemListStatus::Selected)
+                .map(|(name, _)| name.clone())
+                .collect(),
+            use_workspace_dependencies: packages.widget.use_workspace_dependencies,
+        })
+    }
+
+    ///In a workspace the user has to say where the crates go before adding them
+    pub fn has_target_packages(&self) -> bool {
+        self.workspace_target()
+            .is_none_or(|target| !target.packages.is_empty())
+    }
+
+    ///Whether one of the popups on top of the crates list is open
+    pub fn is_showing_popup(&self) -> bool {
+        self.is_showing_features
+            || self.is_showing_versions
+            || self.is_showing_packages
+            || self.is_showing_presets
+            || self.is_saving_profile()
+            || self.is_showing_preview
+            || self.is_showing_readme()
+            || self.is_showing_export()
+    }
+
+    pub fn toggle_show_versions(&mut self) {
+        if let Some(current_crate_selected) = self.get_current_crate_selected() {
+            // Start on the version already chosen so it is easy to change it
+            let chosen = current_crate_selected
+                .version
+                .as_deref()
+                .and_then(|requirement| {
+                    current_crate_selected
+                        .versions
+                        .unwrap_or_default()
+                        .iter()
+                        .position(|version| version.num == requirement_version(requirement))
+                });
+
+            self.versions.state.select(Some(chosen.unwrap_or(0)));
+            self.is_showing_versions.toggle();
+        }
+    }
+
+    pub fn scroll_down_versions(&mut self) {
+        let total = self
+            .get_current_crate_selected()
+            .and_then(|krate| krate.versions)
+            .map_or(0, |versions| versions.len());
+        scroll_wrapping(&mut self.versions.state, total, ScrollDirection::Down);
+    }
+
+    pub fn scroll_up_versions(&mut self) {
+        let total = self
+            .get_current_crate_selected()
+            .and_then(|krate| krate.versions)
+            .map_or(0, |versions| versions.len());
+        scroll_wrapping(&mut self.versions.state, total, ScrollDirection::Up);
+    }
+
+    ///Uses the highlighted version, or goes back to the latest one if it was already chosen
+    pub fn toggle_select_version(&mut self) {
+        let Some(index) = self.versions.state.selected() else {
+            return;
+        };
+
+        if let Some(current_crate) = self.current_crate_mut() {
+            let Some(version) = current_crate
+                .versions
+                .as_ref()
+                .and_then(|versions| versions.get(index))
+            else {
+                return;
+            };
+
+            current_crate.version = match &current_crate.version {
+                Some(requirement) if requirement_version(requirement) == version.num => None,
+                _ => Some(version.num.clone()),
+            };
+        }
+    }
+
+    pub fn cycle_version_requirement(&mut self) {
+        if let Some(current_crate) = self.current_crate_mut() {
+            if let Some(requirement) = &current_crate.version {
+                current_crate.version = Some(cycle_requirement(requirement));
+            }
+        }
+    }
+
+    pub fn toggle_default_features(&mut self) {
+        if let Some(current_crate) = self.current_crate_mut() {
+            current_crate.default_features.toggle();
+        }
+    }
+
+    pub fn show_preview_loading(&mut self) {
+        self.is_showing_preview = true;
+        self.preview.widget = DependenciesPreviewWidget {
+            previews: Vec::new(),
+            is_loading: true,
+        };
+        self.preview.state.select(Some(0));
+    }
+
+    pub fn load_preview(&mut self, previews: Vec<DependencyPreview>) {
+        self.preview.widget = DependenciesPreviewWidget {
+            previews,
+            is_loading: false,
+        };
+    }
+
+    #[inline]
+    pub fn is_loading_preview(&self) -> bool {
+        self.preview.widget.is_loading
+    }
+
+    pub fn close_preview(&mut self) {
+        self.is_showing_preview = false;
+        self.preview = Preview::default();
+    }
+
+    pub fn scroll_down_preview(&mut self) {
+        let total = self.preview.widget.previews.len();
+        scroll_wrapping(&mut self.preview.state, total, ScrollDirection::Down);
+    }
+
+    pub fn scroll_up_preview(&mut self) {
+        let total = self.preview.widget.previews.len();
+        scroll_wrapping(&mut self.preview.state, total, ScrollDirection::Up);
+    }
+
+    ///Takes the highlighted crate out of the preview and out of the crates to add
+    pub fn remove_crate_from_preview(&mut self) {
+        let Some(index) = self.preview.state.selected() else {
+            return;
+        };
+
+        if index >= self.preview.widget.previews.len() {
+            return;
+        }
+
+        let removed = self.preview.widget.previews.remove(index);
+        self.deselect_crate(&removed.crate_name);
+
+        if self.preview.widget.previews.is_empty() {
+            self.close_preview();
+        } else if index >= self.preview.widget.previews.len() {
+            self.preview.state.select(Some(index - 1));
+        }
+    }
+
+    ///A crate already in Cargo.toml goes back to how it is written there
+    pub fn deselect_crate(&mut self, crate_name: &str) {
+        for category in &mut self.categories {
+            for krate in category
+                .crates
+                .iter_mut()
+                .filter(|krate| krate.name == crate_name)
+            {
+                let Some(existing) = krate.existing.clone() else {
+                    krate.status = ItemListStatus::Unselected;
+                    continue;
+                };
+
+                krate.status = ItemListStatus::Selected;
+                krate.default_features = existing.default_features;
+                krate.version = None;
+
+                for feature in krate.features.iter_mut().flatten() {
+                    feature.status = if existing.features.contains(&feature.name) {
+                        ItemListStatus::Selected
+                    } else {
+                        ItemListStatus::Unselected
+                    };
+                }
+            }
+        }
+
+        self.crates_to_add
+            .widget
+            .crates
+            .retain(|crate_to_add| crate_to_add.crate_name != crate_name);
+    }
+
+    fn render_preview_popup(&mut self, area: Rect, buf: &mut Buffer) {
+        let center = centered_rect(80, 60, area);
+
+        Clear.render(center, buf);
+
+        if self.preview.widget.is_loading {
+            Block::bordered().render(center, buf);
+
+            let loader = Throbber::default()
+                .label("Resolving versions with cargo add --dry-run, please wait a moment")
+                .throbber_set(throbber_widgets_tui::BRAILLE_SIX)
+                .use_type(throbber_widgets_tui::WhichUse::Spin);
+
+            StatefulWidget::render(loader, center, buf, &mut self.loader_state);
+        } else {
+            StatefulWidgetRef::render_ref(
+                &self.preview.widget,
+                center,
+                buf,
+                &mut self.preview.state,
+            );
+        }
+    }
+
+    fn render_versions_popup(&mut self, area: Rect, buf: &mut Buffer) {
+        let center = centered_rect(80, 40, area);
+        let Some(current_crate_selected) = self.get_current_crate_selected() else {
+            return;
+        };
+
+        self.versions.widget = VersionsWidgetList::new(
+            current_crate_selected.name,
+            current_crate_selected.versions,
+            current_crate_selected.version,
+        );
+
+        Clear.render(center, buf);
+
+        if current_crate_selected.is_loading {
+            Block::bordered().render(center, buf);
+
+            let loader = Throbber::default()
+                .label(format!(
+                    "Fetching versions of {}, please wait a moment",
+                    self.versions.widget.crate_name
+                ))
+                .throbber_set(throbber_widgets_tui::BRAILLE_SIX)
+                .use_type(throbber_widgets_tui::WhichUse::Spin);
+
+            StatefulWidget::render(loader, center, buf, &mut self.loader_state);
+        } else {
+            StatefulWidgetRef::render_ref(
+                &self.versions.widget,
+                center,
+                buf,
+                &mut self.versions.state,
+            );
+        }
+    }
+
+    fn render_packages_popup(&mut self, area: Rect, buf: &mut Buffer) {
+        if let Some(packages) = self.packages.as_mut() {
+            let center = centered_rect(80, 40, area);
+            Clear.render(center, buf);
+            StatefulWidgetRef::render_ref(&packages.widget, center, buf, &mut packages.state);
+        }
+    }
+
     fn render_features_popup(&mut self, area: Rect, buf: &mut Buffer) {
         let center = centered_rect(80, 40, area);
-        let (current_crate_selected, index_current_crate_selected) =
-            self.get_current_crate_selected().unwrap();
+        let Some(current_crate_selected) = self.get_current_crate_selected() else {
+            return;
+        };
 
         self.features.widget = FeaturesWidgetList::new(
-            index_current_crate_selected,
             current_crate_selected.name,
             current_crate_selected.features,
+            current_crate_selected.default_features,
         );
 
         Clear.render(center, buf);
@@ -3,15 +3,25 @@ use ratatui::{
     style::{palette::tailwind, Style},
     widgets::{
         block::{Block, Padding, Position, Title},
-        Borders,  List, ListDirection, ListItem, ListState, Paragraph,
-        StatefulWidgetRef, Wrap,
+        Borders, List, ListDirection, ListItem, ListState, Paragraph, StatefulWidgetRef, Wrap,
     },
 };
-use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 use throbber_widgets_tui::{Throbber, ThrobberState};
 use tui_widget_list::PreRender;
 
-use crate::dependency_builder::CrateToAdd;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    backend::Curation,
+    cache::CatalogStatus,
+    dependency_builder::{AddResult, Change, CrateToAdd, DependencyKind, DependencyPreview},
+    export::ExportFormat,
+    preset::Preset,
+    project::ExistingDependency,
+    utils::{enabled_by, requirement_version},
+};
 
 use self::style::Stylize;
 
@@ -22,69 +32,23 @@ pub enum ItemListStatus {
     Unselected,
 }
 
-#[derive(
-    Default, Clone, Copy, Display, FromRepr, EnumIter, PartialEq, Eq, PartialOrd, Ord, Debug,
-)]
-pub enum CategoriesWidget {
-    #[strum(to_string = "General")]
-    #[default]
-    General,
-
-    #[strum(to_string = "Common")]
-    Common,
-    #[strum(to_string = "Math-scientific")]
-    Math,
-
-    #[strum(to_string = "FFI")]
-    FFI,
-
-    #[strum(to_string = "Cryptography")]
-    Cryptography,
-
-    #[strum(to_string = "Concurrency")]
-    Concurrency,
-
-    #[strum(to_string = "Networking")]
-    Networking,
-
-    #[strum(to_string = "Databases")]
-    Databases,
-
-    #[strum(to_string = "Cli-tools")]
-    Clis,
-
-    #[strum(to_string = "Graphics")]
-    Graphics,
+///The names of the categories shown in the tab list
+#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CategoriesWidget {
+    pub categories: Vec<String>,
 }
 
-//Heavy use of unwrap here, this is infalle, I think
 impl CategoriesWidget {
-    pub fn next(self) -> Self {
-        let current_index = self as usize;
-        let previous_index = current_index.saturating_add(1);
-        Self::from_repr(previous_index).unwrap_or(Self::from_repr(0).unwrap())
-    }
-
-    pub fn previous(self) -> Self {
-        let current_index = self as usize;
-        let previous_index = current_index.saturating_sub(1);
-        if current_index == 0 {
-            return Self::from_repr(Self::iter().len() - 1).unwrap();
-        }
-
-        Self::from_repr(previous_index).unwrap()
+    pub fn new(categories: Vec<String>) -> Self {
+        Self { categories }
     }
 }
 
-impl StatefulWidget for CategoriesWidget {
+impl StatefulWidgetRef for CategoriesWidget {
     type State = ListState;
 
-    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let categories: Vec<String> = CategoriesWidget::iter()
-            .map(|category| format!("{category}"))
-            .collect();
-
-        let list = List::new(categories)
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let list = List::new(self.categories.clone())
             .style(Style::default().white())
             .highlight_style(Style::default().blue())
             .highlight_symbol(">> ")
@@ -103,6 +67,11 @@ pub struct Popup {
 pub struct FeatureItemList {
     pub name: String,
     pub status: ItemListStatus,
+    ///Enabled by the crate's `default` feature according to crates.io
+    pub is_default: bool,
+    ///What the feature turns on according to crates.io: other features, optional dependencies
+    ///like `dep:serde` and features of dependencies like `serde?/std`
+    pub enables: Vec<String>,
 }
 
 impl FeatureItemList {
@@ -110,47 +79,342 @@ impl FeatureItemList {
         Self {
             name,
             status: ItemListStatus::Unselected,
+            is_default: false,
+            enables: Vec::new(),
+        }
+    }
+
+    ///`enabled_by` is the selected feature that turns this one on, if any
+    fn to_list_item(&self, enabled_by: Option<&String>) -> ListItem<'static> {
+        let default_mark = if self.is_default { " (default)" } else { "" };
+
+        let enables = if self.enables.is_empty() {
+            String::new()
+        } else {
+            format!(" → {}", self.enables.join(", "))
+        };
+
+        let (line, bg_color) = match (&self.status, enabled_by) {
+            (ItemListStatus::Selected, _) => (
+                Line::from(vec![
+                    self.name.clone().black(),
+                    " ✓".black(),
+                    default_mark.black(),
+                    enables.black(),
+                ]),
+                tailwind::BLUE.c300,
+            ),
+            (ItemListStatus::Unselected, Some(enabled_by)) => (
+                Line::from(vec![
+                    self.name.clone().green(),
+                    " ✓".green(),
+                    default_mark.dim(),
+                    format!(" (enabled by {enabled_by})").green().italic(),
+                    enables.dim(),
+                ]),
+                Color::default(),
+            ),
+            (ItemListStatus::Unselected, None) => (
+                Line::from(vec![
+                    self.name.clone().into(),
+                    " ☐".into(),
+                    default_mark.dim(),
+                    enables.dim(),
+                ]),
+                Color::default(),
+            ),
+        };
+
+        ListItem::new(line).style(Style::default().bg(bg_color))
+    }
+}
+
+///A version of a crate published on crates.io
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VersionItemList {
+    pub num: String,
+    ///Unknown for old versions of crates read from the index
+    pub released_at: Option<DateTime<Utc>>,
+    pub yanked: bool,
+}
+
+impl VersionItemList {
+    pub fn new(num: String, released_at: Option<DateTime<Utc>>, yanked: bool) -> Self {
+        Self {
+            num,
+            released_at,
+            yanked,
         }
     }
+
+    ///Versions like `2.0.0-rc.1`
+    pub fn is_prerelease(&self) -> bool {
+        self.num.contains('-')
+    }
 }
 
-impl From<FeatureItemList> for ListItem<'_> {
-    fn from(value: FeatureItemList) -> Self {
-        let (is_selected, bg_color) = match value.status {
-            ItemListStatus::Selected => ("✓", tailwind::BLUE.c300),
-            ItemListStatus::Unselected => ("☐", Color::default()),
+///What crates.io knows about a crate, shown in the details pane
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CrateDetails {
+    ///The version `cargo add` picks by default
+    pub latest_version: String,
+    pub downloads: u64,
+    ///Downloads of the last 90 days
+    pub recent_downloads: Option<u64>,
+    pub updated_at: DateTime<Utc>,
+    pub license: Option<String>,
+    pub repository: Option<String>,
+    pub homepage: Option<String>,
+    ///The minimum supported rust version of the latest version
+    pub rust_version: Option<String>,
+}
+
+///The highlighted crate in full: its crates.io information and what blessed.rs says about it
+#[derive(Debug, Default, Clone)]
+pub struct CrateDetailsWidget {
+    pub krate: Option<CrateItemList>,
+}
+
+impl CrateDetailsWidget {
+    pub fn new(krate: Option<CrateItemList>) -> Self {
+        Self { krate }
+    }
+}
+
+///e.g. `12,345,678`
+fn thousands(number: u64) -> String {
+    let digits = number.to_string();
+    let mut formatted = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+impl Widget for CrateDetailsWidget {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        let block = Block::bordered().title("Details");
+
+        let Some(krate) = self.krate else {
+            Paragraph::new("Highlight a crate to see its details".dim())
+                .block(block)
+                .render(area, buf);
+            return;
+        };
+
+        let field = |name: &'static str, value: String| {
+            Line::from(vec![format!("{name}: ").bold(), value.into()])
         };
 
-        let line = match value.status {
-            ItemListStatus::Selected => {
-                Line::from(vec![value.name.black(), " ".into(), is_selected.black()])
+        let mut lines = vec![Line::from(krate.name.clone().bold().blue())];
+
+        if let Some(registry) = &krate.registry {
+            lines.push(field("Registry", registry.clone()));
+        }
+
+        match &krate.details {
+            Some(details) => {
+                lines.push(field("Latest version", details.latest_version.clone()));
+
+                let recent = details
+                    .recent_downloads
+                    .map(|recent| format!(" ({} in the last 90 days)", thousands(recent)))
+                    .unwrap_or_default();
+                lines.push(field(
+                    "Downloads",
+                    format!("{}{recent}", thousands(details.downloads)),
+                ));
+
+                lines.push(field(
+                    "Updated",
+                    details.updated_at.format("%Y-%m-%d").to_string(),
+                ));
+
+                let not_specified = || "not specified".to_string();
+
+                lines.push(field(
+                    "License",
+                    details.license.clone().unwrap_or_else(not_specified),
+                ));
+                lines.push(field(
+                    "MSRV",
+                    details.rust_version.clone().unwrap_or_else(not_specified),
+                ));
+                lines.push(field(
+                    "Repository",
+                    details.repository.clone().unwrap_or_else(not_specified),
+                ));
+                lines.push(field(
+                    "Homepage",
+                    details.homepage.clone().unwrap_or_else(not_specified),
+                ));
+            }
+            None if krate.is_loading => {
+                lines.push(Line::from("Loading crates.io information...".dim()));
             }
-            ItemListStatus::Unselected => {
-                Line::from(vec![value.name.into(), " ".into(), is_selected.into()])
+            // Only crates.io knows about downloads, licenses and links, the index has the versions
+            None if krate.registry.is_some() => {
+                if let Some(latest) = krate
+                    .versions
+                    .iter()
+                    .flatten()
+                    .find(|version| !version.yanked)
+                {
+                    lines.push(field("Latest version", latest.num.clone()));
+                }
             }
+            None => lines.push(Line::from("crates.io information not available".dim())),
+        }
+
+        lines.push(Line::default());
+
+        if !krate.use_case.is_empty() {
+            lines.push(Line::from(krate.use_case.clone().bold().yellow()));
+        }
+        lines.push(Line::from(krate.description));
+
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
+
+///The versions of a crate, newest first, and the requirement the user chose from them
+#[derive(Debug, Default, Clone)]
+pub struct VersionsWidgetList {
+    pub crate_name: String,
+    pub versions: Option<Vec<VersionItemList>>,
+    pub requirement: Option<String>,
+}
+
+impl VersionsWidgetList {
+    pub fn new(
+        crate_name: String,
+        versions: Option<Vec<VersionItemList>>,
+        requirement: Option<String>,
+    ) -> Self {
+        Self {
+            crate_name,
+            versions,
+            requirement,
+        }
+    }
+}
+
+impl StatefulWidgetRef for VersionsWidgetList {
+    type State = ListState;
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        Block::bordered()
+            .title(format!("Versions of crate: {}", self.crate_name))
+            .title_bottom(Line::from(vec![
+                "Use version ".into(),
+                "<s> ".bold().blue(),
+                "Requirement ^ = ~ ".into(),
+                "<o> ".bold().blue(),
+                "Move down ".into(),
+                "<Down> <j> ".bold().blue(),
+                "Move up ".into(),
+                "<Up> <k> ".bold().blue(),
+                "Close ".into(),
+                "<v>".bold().blue(),
+            ]))
+            .render(area, buf);
+
+        let inner_area = area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+
+        let [requirement_area, versions_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner_area);
+
+        let requirement = match &self.requirement {
+            Some(requirement) => Line::from(vec![
+                "Version requirement: ".into(),
+                requirement.clone().bold().green(),
+            ]),
+            None => Line::from(vec![
+                "Version requirement: ".into(),
+                "latest, chosen by cargo add".dim(),
+            ]),
         };
 
-        ListItem::new(line).style(Style::default().bg(bg_color))
+        Paragraph::new(requirement).render(requirement_area, buf);
+
+        let Some(versions) = &self.versions else {
+            Paragraph::new("Could not get the versions of this crate").render(versions_area, buf);
+            return;
+        };
+
+        let chosen = self.requirement.as_deref().map(requirement_version);
+
+        let items: Vec<ListItem> = versions
+            .iter()
+            .map(|version| {
+                let is_chosen = chosen == Some(version.num.as_str());
+
+                let mut line = Line::from(vec![
+                    format!("{:<20}", version.num).into(),
+                    if is_chosen { "✓ " } else { "☐ " }.into(),
+                    version
+                        .released_at
+                        .map(|released_at| released_at.format("%Y-%m-%d").to_string())
+                        .unwrap_or_default()
+                        .dim(),
+                ]);
+
+                if version.yanked {
+                    line.spans.push(" [yanked]".bold().red());
+                }
+
+                if version.is_prerelease() {
+                    line.spans.push(" [pre-release]".bold().yellow());
+                }
+
+                let item = ListItem::new(line);
+
+                if is_chosen {
+                    item.style(Style::default().bg(tailwind::BLUE.c300).black())
+                } else {
+                    item
+                }
+            })
+            .collect();
+
+        let versions_list = List::new(items)
+            .highlight_symbol(">> ")
+            .direction(ListDirection::TopToBottom);
+
+        StatefulWidget::render(versions_list, versions_area, buf, state);
     }
 }
 
 #[derive(Debug, Default, Clone)]
 pub struct FeaturesWidgetList {
-    pub index_current_crate: usize,
     pub crate_name: String,
     pub features: Option<Vec<FeatureItemList>>,
+    pub default_features: bool,
 }
 
 impl FeaturesWidgetList {
     pub fn new(
-        index_current_crate: usize,
         crate_name: String,
         features: Option<Vec<FeatureItemList>>,
+        default_features: bool,
     ) -> Self {
         Self {
-            index_current_crate,
             crate_name,
             features,
+            default_features,
         }
     }
 }
@@ -167,6 +431,8 @@ impl StatefulWidgetRef for FeaturesWidgetList {
                 "<Down> <j> ".bold().blue(),
                 "Move up ".into(),
                 "<Up> <k> ".bold().blue(),
+                "Toggle default features ".into(),
+                "<n> ".bold().blue(),
                 "Close ".into(),
                 "<f>".bold().blue(),
             ]))
@@ -177,16 +443,48 @@ impl StatefulWidgetRef for FeaturesWidgetList {
             horizontal: 1,
         });
 
+        let [default_features_area, features_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner_area);
+
+        let default_features = if self.default_features {
+            Line::from(vec!["Default features: ".into(), "on".bold().green()])
+        } else {
+            Line::from(vec![
+                "Default features: ".into(),
+                "off".bold().red(),
+                ", the ones marked (default) are only enabled if selected".dim(),
+            ])
+        };
+
+        Paragraph::new(default_features).render(default_features_area, buf);
+
         match &self.features {
             Some(features) => {
-                let features_list = List::new(features.clone())
+                let graph: HashMap<String, Vec<String>> = features
+                    .iter()
+                    .map(|feature| (feature.name.clone(), feature.enables.clone()))
+                    .collect();
+
+                let enabled = enabled_by(
+                    &graph,
+                    features
+                        .iter()
+                        .filter(|feature| feature.status == ItemListStatus::Selected)
+                        .map(|feature| feature.name.as_str()),
+                );
+
+                let items = features
+                    .iter()
+                    .map(|feature| feature.to_list_item(enabled.get(&feature.name)));
+
+                let features_list = List::new(items)
                     .highlight_symbol(">> ")
                     .direction(ListDirection::TopToBottom);
 
-                StatefulWidget::render(features_list, inner_area, buf, state);
+                StatefulWidget::render(features_list, features_area, buf, state);
             }
-            None => Paragraph::new("This crate has no features").render(inner_area, buf),
-        };
+            None => Paragraph::new("This crate has no features").render(features_area, buf),
+        }
     }
 }
 
@@ -220,7 +518,23 @@ impl StatefulWidgetRef for Popup {
 pub struct CrateItemList {
     pub name: String,
     pub description: String,
+    ///What blessed.rs recommends this crate for, crates are grouped by it
+    pub use_case: String,
+    pub curation: Curation,
     pub features: Option<Vec<FeatureItemList>>,
+    ///`false` when the user wants the crate without its default features
+    pub default_features: bool,
+    pub versions: Option<Vec<VersionItemList>>,
+    ///The version requirement chosen from `versions`, `None` to add the latest one
+    pub version: Option<String>,
+    pub kind: DependencyKind,
+    ///The crate is already in the project's Cargo.toml
+    pub existing: Option<ExistingDependency>,
+    ///The registry of `.cargo/config.toml` publishing the crate, `None` for crates.io
+    pub registry: Option<String>,
+    ///The categories listing the crate, shown when it is a search result
+    pub found_in: Option<String>,
+    pub details: Option<CrateDetails>,
     pub status: ItemListStatus,
     pub highlight_style: String,
     pub is_loading: bool,
@@ -246,11 +560,42 @@ impl ratatui::widgets::Widget for CrateItemList {
 
         name.bold().blue().render(name_area, buf);
 
-        let description = match self.status {
-            ItemListStatus::Unselected => self.description.into(),
-            ItemListStatus::Selected => self.description.black(),
+        let badge = match self.curation {
+            Curation::Upstream => Span::default(),
+            Curation::TeamApproved => "[team approved] ".bold().green(),
+            Curation::TeamDiscouraged => "[discouraged by team] ".bold().red(),
+        };
+
+        let already_added = self
+            .already_added_label()
+            .map(|label| label.bold().yellow());
+        let found_in = self
+            .found_in
+            .as_ref()
+            .map(|categories| format!("[{categories}] ").bold().magenta());
+        let registry = self
+            .registry
+            .as_ref()
+            .map(|registry| format!("[registry: {registry}] ").bold().cyan());
+
+        let mut description = match self.status {
+            ItemListStatus::Unselected => Text::from(self.description),
+            ItemListStatus::Selected => Text::from(self.description).black(),
         };
 
+        if let Some(first_line) = description.lines.first_mut() {
+            first_line.spans.insert(0, badge);
+            if let Some(registry) = registry {
+                first_line.spans.insert(0, registry);
+            }
+            if let Some(already_added) = already_added {
+                first_line.spans.insert(0, already_added);
+            }
+            if let Some(found_in) = found_in {
+                first_line.spans.insert(0, found_in);
+            }
+        }
+
         Paragraph::new(description)
             .block(Block::default().borders(Borders::BOTTOM))
             .style(Style::new().bg(bg_color))
@@ -271,11 +616,13 @@ impl PreRender for CrateItemList {
 #[derive(Clone, Default)]
 pub struct CratesToAddListWidget {
     pub crates: Vec<CrateToAdd>,
+    ///The user is moving through this list instead of the crates list
+    pub is_focused: bool,
 }
 
 impl CratesToAddListWidget {
-    pub fn new(crates: Vec<CrateToAdd>) -> Self {
-        Self { crates }
+    pub fn new(crates: Vec<CrateToAdd>, is_focused: bool) -> Self {
+        Self { crates, is_focused }
     }
 }
 
@@ -286,15 +633,54 @@ impl StatefulWidgetRef for CratesToAddListWidget {
             .crates
             .iter()
             .cloned()
-            .map(|dep| Line::from(vec![dep.crate_name.into(), " ✓ ".blue()]))
+            .map(|dep| {
+                let mut line = match dep.change {
+                    Change::Add => Line::from(vec![dep.crate_name.into(), " ✓ ".blue()]),
+                    Change::Update { .. } => {
+                        Line::from(vec![dep.crate_name.into(), " [update] ".bold().yellow()])
+                    }
+                    Change::Remove => {
+                        return Line::from(vec![dep.crate_name.into(), " [remove]".bold().red()]);
+                    }
+                };
+                if let Some(version) = &dep.version {
+                    line.spans.push(format!("{version} ").into());
+                }
+                match dep.kind {
+                    DependencyKind::Normal => {}
+                    DependencyKind::Dev => line.spans.push("[dev] ".bold().magenta()),
+                    DependencyKind::Build => line.spans.push("[build] ".bold().cyan()),
+                }
+                if !dep.default_features {
+                    line.spans.push("no default features".dim());
+                }
+                line
+            })
             .collect();
 
+        let mut block = Block::bordered()
+            .padding(Padding::uniform(2))
+            .title("Dependencies to add");
+
+        if self.is_focused {
+            block = block
+                .border_style(Style::default().blue())
+                .title_bottom(Line::from(vec![
+                    "Dev / build ".into(),
+                    "<t> ".bold().blue(),
+                    "Remove ".into(),
+                    "<s> ".bold().blue(),
+                    "Save as profile ".into(),
+                    "<S> ".bold().blue(),
+                    "Export ".into(),
+                    "<e> ".bold().blue(),
+                    "Back ".into(),
+                    "<Left> <h>".bold().blue(),
+                ]));
+        }
+
         let list = List::new(items)
-            .block(
-                Block::bordered()
-                    .padding(Padding::uniform(2))
-                    .title("Dependencies to add"),
-            )
+            .block(block)
             .highlight_style(Style::default().blue())
             .highlight_symbol("* ")
             .direction(ListDirection::TopToBottom);
@@ -304,16 +690,123 @@ impl StatefulWidgetRef for CratesToAddListWidget {
 }
 
 impl CrateItemList {
+    ///e.g. `[already added, v1.0.200 with derive] [adds rc]` when the selection changes the
+    ///crate's entry in Cargo.toml
+    fn already_added_label(&self) -> Option<String> {
+        let existing = self.existing.as_ref()?;
+
+        let version = existing
+            .version()
+            .map(|version| format!(", v{}", version.trim_start_matches('^')))
+            .unwrap_or_default();
+
+        let features = if existing.features.is_empty() {
+            String::new()
+        } else {
+            format!(" with {}", existing.features.join(", "))
+        };
+
+        let mut labels = vec![format!("[already added{version}{features}]")];
+        labels.extend(self.changes());
+
+        Some(format!("{} ", labels.join(" ")))
+    }
+
+    ///What the selection would change in the entry of a crate already in Cargo.toml
+    fn changes(&self) -> Vec<String> {
+        let Some(existing) = &self.existing else {
+            return Vec::new();
+        };
+
+        if self.status == ItemListStatus::Unselected {
+            return vec!["[will be removed]".to_string()];
+        }
+
+        let mut changes = Vec::new();
+
+        let new_features: Vec<&str> = self
+            .features
+            .iter()
+            .flatten()
+            .filter(|feature| {
+                feature.status == ItemListStatus::Selected
+                    && !existing.features.contains(&feature.name)
+            })
+            .map(|feature| feature.name.as_str())
+            .collect();
+
+        if !new_features.is_empty() {
+            changes.push(format!("[adds {}]", new_features.join(", ")));
+        }
+
+        let removed_features = self.removed_features();
+
+        if !removed_features.is_empty() {
+            changes.push(format!("[drops {}]", removed_features.join(", ")));
+        }
+
+        // cargo add never turns default features back on, so only turning them off is a change
+        if existing.default_features && !self.default_features {
+            changes.push("[turns default features off]".to_string());
+        }
+
+        if let Some(version) = self
+            .version
+            .as_ref()
+            .filter(|version| existing.requirement.as_ref() != Some(*version))
+        {
+            changes.push(format!("[requires {version}]"));
+        }
+
+        changes
+    }
+
+    ///The features of a crate already in Cargo.toml the user unselected, none until its features
+    ///are loaded
+    pub fn removed_features(&self) -> Vec<String> {
+        let (Some(existing), Some(features)) = (&self.existing, &self.features) else {
+            return Vec::new();
+        };
+
+        existing
+            .features
+            .iter()
+            .filter(|name| {
+                !features.iter().any(|feature| {
+                    &feature.name == *name && feature.status == ItemListStatus::Selected
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    ///A crate already in Cargo.toml is only added again or removed when the user changed it
+    pub fn is_changed(&self) -> bool {
+        self.existing.is_none() || !self.changes().is_empty()
+    }
+
     pub fn new(
         name: String,
         description: String,
+        use_case: String,
+        curation: Curation,
         status: ItemListStatus,
         features: Option<Vec<FeatureItemList>>,
     ) -> Self {
         Self {
             name,
             description,
+            use_case,
+            curation,
             features,
+            default_features: true,
+            versions: None,
+            version: None,
+            kind: DependencyKind::default(),
+            existing: None,
+            registry: None,
+            found_in: None,
+            details: None,
             status,
             is_loading: true,
             highlight_style: String::default(),
@@ -321,40 +814,592 @@ impl CrateItemList {
     }
 }
 
+///A row of the crates list, either the header of a use case or one of its crates
+#[derive(Clone, Debug)]
+pub enum CrateListItem {
+    Section {
+        use_case: String,
+        total_crates: usize,
+        is_collapsed: bool,
+        highlight_style: String,
+    },
+    Crate(Box<CrateItemList>),
+}
+
+impl CrateListItem {
+    pub fn section(use_case: String, total_crates: usize, is_collapsed: bool) -> Self {
+        Self::Section {
+            use_case,
+            total_crates,
+            is_collapsed,
+            highlight_style: String::default(),
+        }
+    }
+}
+
+impl Widget for CrateListItem {
+    fn render(self, area: Rect, buf: &mut Buffer)
+    where
+        Self: Sized,
+    {
+        match self {
+            CrateListItem::Crate(crate_item) => crate_item.render(area, buf),
+            CrateListItem::Section {
+                use_case,
+                total_crates,
+                is_collapsed,
+                highlight_style,
+            } => {
+                let fold_symbol = if is_collapsed { "▶" } else { "▼" };
+
+                Line::from(vec![
+                    format!("{highlight_style} {fold_symbol} ").yellow(),
+                    use_case.bold().yellow(),
+                    format!(" ({total_crates})").dim(),
+                ])
+                .render(area, buf);
+            }
+        }
+    }
+}
+
+impl PreRender for CrateListItem {
+    fn pre_render(&mut self, context: &tui_widget_list::PreRenderContext) -> u16 {
+        match self {
+            CrateListItem::Crate(crate_item) => crate_item.pre_render(context),
+            CrateListItem::Section {
+                highlight_style, ..
+            } => {
+                if context.is_selected {
+                    *highlight_style = ">>".to_string();
+                }
+                2
+            }
+        }
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct CratesListWidget {
-    pub crates: Vec<CrateItemList>,
+    pub items: Vec<CrateListItem>,
 }
 
 impl StatefulWidgetRef for CratesListWidget {
     type State = tui_widget_list::ListState;
     fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-        let list = tui_widget_list::List::new(self.crates.clone());
+        let list = tui_widget_list::List::new(self.items.clone());
 
         StatefulWidget::render(list, area, buf, state);
     }
 }
 
 impl CratesListWidget {
-    pub fn new(crates: &[CrateItemList]) -> Self {
-        Self {
-            crates: crates.to_vec(),
+    pub fn new(items: Vec<CrateListItem>) -> Self {
+        Self { items }
+    }
+}
+
+///The packages of the workspace the selected crates are added to
+#[derive(Debug, Default, Clone)]
+pub struct PackagesWidget {
+    ///Every package of the workspace and whether the crates are added to it
+    pub packages: Vec<(String, ItemListStatus)>,
+    pub use_workspace_dependencies: bool,
+}
+
+impl StatefulWidgetRef for PackagesWidget {
+    type State = ListState;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        Block::bordered()
+            .title("Packages of the workspace to add the crates to")
+            .title_bottom(Line::from(vec![
+                "Toggle select ".into(),
+                "<s> ".bold().blue(),
+                "Use [workspace.dependencies] ".into(),
+                "<u> ".bold().blue(),
+                "Move down ".into(),
+                "<Down> <j> ".bold().blue(),
+                "Move up ".into(),
+                "<Up> <k> ".bold().blue(),
+                "Close ".into(),
+                "<w>".bold().blue(),
+            ]))
+            .render(area, buf);
+
+        let inner_area = area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+
+        let [workspace_dependencies_area, packages_area] =
+            Layout::vertical([Constraint::Length(2), Constraint::Fill(1)]).areas(inner_area);
+
+        let workspace_dependencies = if self.use_workspace_dependencies {
+            Line::from(vec![
+                "[workspace.dependencies]: ".into(),
+                "on".bold().green(),
+                ", the versions go to the root Cargo.toml and the packages use workspace = true"
+                    .dim(),
+            ])
+        } else {
+            Line::from(vec![
+                "[workspace.dependencies]: ".into(),
+                "off".bold().red(),
+            ])
+        };
+
+        Paragraph::new(workspace_dependencies).render(workspace_dependencies_area, buf);
+
+        let items: Vec<ListItem> = self
+            .packages
+            .iter()
+            .map(|(name, status)| match status {
+                ItemListStatus::Selected => {
+                    ListItem::new(Line::from(vec![name.clone().black(), " ✓".black()]))
+                        .style(Style::default().bg(tailwind::BLUE.c300))
+                }
+                ItemListStatus::Unselected => {
+                    ListItem::new(Line::from(vec![name.clone().into(), " ☐".into()]))
+                }
+            })
+            .collect();
+
+        let list = List::new(items)
+            .highlight_symbol(">> ")
+            .direction(ListDirection::TopToBottom);
+
+        StatefulWidget::render(list, packages_area, buf, state);
+    }
+}
+
+///The bundled presets and the ones of the config file, applying one selects its crates. The
+///profiles the user saved are listed after them, restoring one replaces the selection
+#[derive(Debug, Default, Clone)]
+pub struct PresetsWidget {
+    pub presets: Vec<Preset>,
+    pub profiles: Vec<Preset>,
+}
+
+impl StatefulWidgetRef for PresetsWidget {
+    type State = ListState;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let block = Block::bordered()
+            .title("Presets and saved profiles")
+            .title_bottom(Line::from(vec![
+                "Apply ".into(),
+                "<Enter> ".bold().blue(),
+                "Move down ".into(),
+                "<Down> <j> ".bold().blue(),
+                "Move up ".into(),
+                "<Up> <k> ".bold().blue(),
+                "Close ".into(),
+                "<p>".bold().blue(),
+            ]));
+
+        let presets = self.presets.iter().map(|preset| (preset, Span::default()));
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|profile| (profile, "[profile] ".bold().magenta()));
+
+        let items: Vec<ListItem> = presets
+            .chain(profiles)
+            .map(|(preset, badge)| {
+                ListItem::new(vec![
+                    Line::from(vec![
+                        badge,
+                        preset.name.clone().bold().blue(),
+                        format!(" {}", preset.description).into(),
+                    ]),
+                    Line::from(format!("   {}", preset.summary()).dim()),
+                ])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_symbol(">> ")
+            .direction(ListDirection::TopToBottom);
+
+        StatefulWidget::render(list, area, buf, state);
+    }
+}
+
+///Asks for the name of the profile the selection is saved as, then tells where it was saved
+#[derive(Debug, Default, Clone)]
+pub struct SaveProfileWidget {
+    pub name: String,
+    ///A profile with this name was already saved, `<Enter>` again replaces it
+    pub replaces_existing: bool,
+    ///`None` while the name is being typed, then the file written or why it could not be
+    pub saved: Option<Result<String, String>>,
+}
+
+impl Widget for &SaveProfileWidget {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered().title("Save the selection as a profile");
+
+        let lines = match &self.saved {
+            None if self.replaces_existing => vec![
+                Line::from(vec!["Name: ".bold(), self.name.clone().into(), "_".into()]),
+                Line::from(format!("There is already a profile called {}", self.name).yellow()),
+                Line::from(vec![
+                    "Replace it ".into(),
+                    "<Enter> ".bold().blue(),
+                    "Cancel ".into(),
+                    "<Esc>".bold().blue(),
+                ]),
+            ],
+            None => vec![
+                Line::from(vec!["Name: ".bold(), self.name.clone().into(), "_".into()]),
+                Line::default(),
+                Line::from(vec![
+                    "Save ".into(),
+                    "<Enter> ".bold().blue(),
+                    "Cancel ".into(),
+                    "<Esc>".bold().blue(),
+                ]),
+            ],
+            Some(Ok(path)) => vec![
+                Line::from(format!("Saved as {}", self.name).green()),
+                Line::from(path.clone().dim()),
+                Line::from("Restore it from the presets <p> or with add --profile".dim()),
+            ],
+            Some(Err(e)) => vec![Line::from(format!("Could not save the profile: {e}").red())],
+        };
+
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: true })
+            .render(area, buf);
+    }
+}
+
+///Shows what would be written to Cargo.toml before adding the selected crates
+#[derive(Debug, Default, Clone)]
+pub struct DependenciesPreviewWidget {
+    pub previews: Vec<DependencyPreview>,
+    pub is_loading: bool,
+}
+
+impl DependencyPreview {
+    fn lines(&self) -> Vec<Line<'static>> {
+        let mut lines: Vec<Line> = Vec::new();
+
+        if let Some(error) = &self.error {
+            lines.push(Line::from(format!("✗ {}: {error}", self.crate_name).red()));
+        } else {
+            if let Some(current_entry) = &self.current_entry {
+                lines.push(Line::from(format!("- {current_entry}").red()));
+            }
+
+            if self.is_removal {
+                return lines;
+            }
+
+            lines.push(Line::from(format!("+ {}", self.new_entry()).green()));
+
+            if let Some(workspace_entry) = &self.workspace_entry {
+                lines.push(Line::from(
+                    format!("+ {workspace_entry} in [workspace.dependencies]").green(),
+                ));
+            }
+
+            if !self.enabled_features.is_empty() {
+                lines.push(Line::from(
+                    format!("    enabled features: {}", self.enabled_features.join(", ")).dim(),
+                ));
+            }
         }
+
+        lines
+    }
+}
+
+impl StatefulWidgetRef for DependenciesPreviewWidget {
+    type State = ListState;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        Block::bordered()
+            .title("Changes to Cargo.toml")
+            .title_bottom(Line::from(vec![
+                "Add ".into(),
+                "<Enter> ".bold().blue(),
+                "Remove crate ".into(),
+                "<s> ".bold().blue(),
+                "Move down ".into(),
+                "<Down> <j> ".bold().blue(),
+                "Move up ".into(),
+                "<Up> <k> ".bold().blue(),
+                "Cancel ".into(),
+                "<Esc> <q>".bold().blue(),
+            ]))
+            .render(area, buf);
+
+        let inner_area = area.inner(&Margin {
+            vertical: 1,
+            horizontal: 1,
+        });
+
+        // The previews come grouped by table, the first crate of each table carries its header
+        let items: Vec<ListItem> = self
+            .previews
+            .iter()
+            .enumerate()
+            .map(|(index, preview)| {
+                let mut lines = Vec::new();
+
+                if index == 0 || self.previews[index - 1].table_header() != preview.table_header() {
+                    if index > 0 {
+                        lines.push(Line::default());
+                    }
+                    lines.push(Line::from(preview.table_header().bold()));
+                }
+
+                lines.extend(preview.lines());
+
+                ListItem::new(lines)
+            })
+            .collect();
+
+        let list = List::new(items)
+            .highlight_symbol(">> ")
+            .direction(ListDirection::TopToBottom);
+
+        StatefulWidget::render(list, inner_area, buf, state);
+    }
+}
+
+///Which crates `cargo add` could add and why the others failed
+#[derive(Debug, Default, Clone)]
+pub struct AddResultsWidget {
+    pub results: Vec<AddResult>,
+    ///Only offered when some crate failed and Cargo.toml was backed up
+    pub can_restore: bool,
+    ///Tells whether restoring Cargo.toml worked, once the user asks for it
+    pub restore_message: Option<String>,
+}
+
+impl AddResultsWidget {
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|result| result.error.is_some())
+    }
+
+    ///How many lines the results take without wrapping, it limits how far they can be scrolled
+    pub fn lines_count(&self) -> usize {
+        self.results
+            .iter()
+            .map(|result| {
+                1 + result
+                    .error
+                    .as_ref()
+                    .map_or(0, |error| error.lines().count())
+            })
+            .sum()
+    }
+}
+
+impl StatefulWidgetRef for AddResultsWidget {
+    ///How many lines the results are scrolled down
+    type State = u16;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let title = if self.has_failures() {
+            "Some dependencies could not be added ✗".bold().red()
+        } else {
+            "Dependencies added successfully ✓".bold().green()
+        };
+
+        let mut instructions = vec!["Move down ".into(), "<Down> <j> ".bold().blue()];
+        instructions.extend(["Move up ".into(), "<Up> <k> ".bold().blue()]);
+
+        if self.can_restore {
+            instructions.extend(["Restore Cargo.toml ".into(), "<r> ".bold().blue()]);
+        }
+
+        instructions.extend(["Quit ".into(), "<Enter> <q>".bold().blue()]);
+
+        let mut lines: Vec<Line> = Vec::new();
+
+        if let Some(restore_message) = &self.restore_message {
+            lines.push(Line::from(restore_message.clone().bold().yellow()));
+            lines.push(Line::default());
+        }
+
+        for result in &self.results {
+            let added_to = match &result.package {
+                Some(package) => format!("{} → {package}", result.crate_name),
+                None => result.crate_name.clone(),
+            };
+
+            match &result.error {
+                Some(error) => {
+                    lines.push(Line::from(format!("✗ {added_to}").red()));
+                    lines.extend(
+                        error
+                            .lines()
+                            .map(|line| Line::from(format!("    {line}").dim())),
+                    );
+                }
+                None => lines.push(Line::from(format!("✓ {added_to}").green())),
+            }
+        }
+
+        Paragraph::new(lines)
+            .block(
+                Block::bordered()
+                    .title(title)
+                    .title_bottom(Line::from(instructions)),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((*state, 0))
+            .render(area, buf);
+    }
+}
+
+///What the README popup shows while the package is downloaded and once it is
+#[derive(Debug, Clone)]
+pub enum ReadmeContent {
+    Loading,
+    Loaded(Vec<Line<'static>>),
+    Failed(String),
+}
+
+///The README a crate was published with, rendered from its markdown
+#[derive(Debug, Clone)]
+pub struct ReadmeWidget {
+    pub crate_name: String,
+    pub version: String,
+    pub content: ReadmeContent,
+}
+
+impl StatefulWidgetRef for ReadmeWidget {
+    ///How many lines the README is scrolled down, it never goes past its last line
+    type State = u16;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let title = Line::from(vec![
+            " README of ".into(),
+            self.crate_name.clone().bold().yellow(),
+            format!(" v{} ", self.version).into(),
+        ]);
+
+        let mut instructions = vec!["Move down ".into(), "<Down> <j> ".bold().blue()];
+        instructions.extend(["Move up ".into(), "<Up> <k> ".bold().blue()]);
+        instructions.extend(["Move a page ".into(), "<PageDown> <PageUp> ".bold().blue()]);
+        instructions.extend(["Close ".into(), "<i> <q>".bold().blue()]);
+
+        let lines = match &self.content {
+            ReadmeContent::Loading => vec![Line::from(
+                "Downloading the README from crates.io...".yellow(),
+            )],
+            ReadmeContent::Loaded(lines) => lines.clone(),
+            ReadmeContent::Failed(error) => vec![Line::from(error.clone().red())],
+        };
+
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom(Line::from(instructions))
+            .padding(Padding::horizontal(1));
+
+        let inner_area = block.inner(area);
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+
+        let max_scroll = paragraph
+            .line_count(inner_area.width)
+            .saturating_sub(usize::from(inner_area.height));
+
+        *state = (*state).min(u16::try_from(max_scroll).unwrap_or(u16::MAX));
+
+        paragraph.block(block).scroll((*state, 0)).render(area, buf);
+    }
+}
+
+///The selected crates in the format they are exported in, before copying or writing them
+#[derive(Debug, Default, Clone)]
+pub struct ExportWidget {
+    pub format: ExportFormat,
+    ///`None` while the latest versions are looked up, an error when one is not known
+    pub contents: Option<Result<String, String>>,
+    ///Where the export went or why it could not, `None` until it is copied or written
+    pub status: Option<Result<String, String>>,
+}
+
+impl StatefulWidgetRef for ExportWidget {
+    ///How many lines the export is scrolled down, it never goes past its last line
+    type State = u16;
+
+    fn render_ref(&self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let title = Line::from(vec![
+            " Export as ".into(),
+            self.format.name().bold().yellow(),
+            " ".into(),
+        ]);
+
+        let mut instructions = vec!["Format ".into(), "<Tab> ".bold().blue()];
+        instructions.extend(["Copy ".into(), "<Enter> ".bold().blue()]);
+        instructions.extend([
+            format!("Write {} ", self.format.file_name()).into(),
+            "<w> ".bold().blue(),
+        ]);
+        instructions.extend(["Close ".into(), "<e> <q>".bold().blue()]);
+
+        let mut lines = match &self.status {
+            Some(Ok(message)) => vec![Line::from(message.clone().green()), Line::default()],
+            Some(Err(e)) => e
+                .lines()
+                .map(|line| Line::from(line.to_string().red()))
+                .chain([Line::default()])
+                .collect(),
+            None => Vec::new(),
+        };
+
+        match &self.contents {
+            Some(Ok(contents)) => {
+                lines.extend(contents.lines().map(|line| Line::from(line.to_string())));
+            }
+            Some(Err(e)) => lines.push(Line::from(e.clone().red())),
+            None => lines.push(Line::from("Looking up the latest versions...")),
+        }
+
+        let block = Block::bordered()
+            .title(title)
+            .title_bottom(Line::from(instructions))
+            .padding(Padding::horizontal(1));
+
+        let inner_area = block.inner(area);
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false });
+
+        let max_scroll = paragraph
+            .line_count(inner_area.width)
+            .saturating_sub(usize::from(inner_area.height));
+
+        *state = (*state).min(u16::try_from(max_scroll).unwrap_or(u16::MAX));
+
+        paragraph.block(block).scroll((*state, 0)).render(area, buf);
     }
 }
 
 #[derive(Debug)]
 pub struct FooterInstructions<'a> {
     instructions: Vec<Span<'a>>,
+    catalog_status: CatalogStatus,
 }
 
 impl<'a> FooterInstructions<'a> {
-    pub fn new(instructions: Vec<Span<'a>>) -> Self {
-        FooterInstructions { instructions }
+    pub fn new(instructions: Vec<Span<'a>>, catalog_status: CatalogStatus) -> Self {
+        FooterInstructions {
+            instructions,
+            catalog_status,
+        }
     }
 }
 
-impl<'a> Widget for FooterInstructions<'a> {
+impl Widget for FooterInstructions<'_> {
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
@@ -367,13 +1412,31 @@ impl<'a> Widget for FooterInstructions<'a> {
             .position(Position::Top)
             .alignment(Alignment::Right);
 
+        let catalog_status = match self.catalog_status {
+            CatalogStatus::UpToDate => Line::default(),
+            CatalogStatus::Refreshing(_) => Line::from("Refreshing crates list... ".dim()),
+            CatalogStatus::Offline(fetched_at) => Line::from(
+                format!(
+                    "offline / data from {} ",
+                    CatalogStatus::fetched_at_label(fetched_at)
+                )
+                .bold()
+                .yellow(),
+            ),
+        };
+
         let block = Block::bordered()
             .title(
                 instructions
                     .alignment(Alignment::Center)
                     .position(Position::Bottom),
             )
-            .title(info);
+            .title(info)
+            .title(
+                Title::from(catalog_status)
+                    .position(Position::Top)
+                    .alignment(Alignment::Left),
+            );
 
         block.render(area, buf);
     }
@@ -0,0 +1,199 @@
+/// This module's job is to find the packages of the user's cargo workspace and to write the
+/// crates shared by them to `[workspace.dependencies]`
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::Deserialize;
+use toml_edit::{DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::dependency_builder::manifest_path;
+
+///A package of the workspace crates can be added to
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+pub struct Member {
+    pub name: String,
+    pub manifest_path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Workspace {
+    ///The Cargo.toml with the `[workspace]` table
+    pub root_manifest: PathBuf,
+    pub members: Vec<Member>,
+    ///The package of the current directory, `None` at the root of a virtual workspace
+    pub current_package: Option<String>,
+}
+
+///The part of `cargo metadata`'s output needed to find the workspace members
+#[derive(Deserialize)]
+struct Metadata {
+    packages: Vec<MetadataPackage>,
+    workspace_members: Vec<String>,
+    workspace_root: PathBuf,
+}
+
+#[derive(Deserialize)]
+struct MetadataPackage {
+    id: String,
+    #[serde(flatten)]
+    member: Member,
+}
+
+impl Workspace {
+    pub fn detect() -> io::Result<Self> {
+        let output = Command::new("cargo")
+            .args(["metadata", "--no-deps", "--format-version", "1"])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(io::Error::other(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        let current_manifest = manifest_path().ok();
+
+        Ok(Self::from_metadata(
+            serde_json::from_slice(&output.stdout)?,
+            current_manifest,
+        ))
+    }
+
+    fn from_metadata(metadata: Metadata, current_manifest: Option<PathBuf>) -> Self {
+        let members: Vec<Member> = metadata
+            .packages
+            .into_iter()
+            .filter(|package| metadata.workspace_members.contains(&package.id))
+            .map(|package| package.member)
+            .collect();
+
+        let current_package = current_manifest.and_then(|current_manifest| {
+            members
+                .iter()
+                .find(|member| member.manifest_path == current_manifest)
+                .map(|member| member.name.clone())
+        });
+
+        Self {
+            root_manifest: metadata.workspace_root.join("Cargo.toml"),
+            members,
+            current_package,
+        }
+    }
+
+    ///Only then the user has to choose where the crates go, otherwise it is the current package
+    pub fn has_many_packages(&self) -> bool {
+        self.members.len() > 1 || self.current_package.is_none()
+    }
+
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name == name)
+    }
+}
+
+///Writes `crate_name = "version"` to `[workspace.dependencies]` unless the crate is already there,
+///keeping the rest of the manifest as the user wrote it. Returns whether the manifest changed
+pub fn add_workspace_dependency(
+    root_manifest: &Path,
+    crate_name: &str,
+    version: &str,
+    default_features: bool,
+    registry: Option<&str>,
+) -> io::Result<bool> {
+    let mut manifest: DocumentMut = fs::read_to_string(root_manifest)?
+        .parse()
+        .map_err(io::Error::other)?;
+
+    let workspace = manifest
+        .get_mut("workspace")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| io::Error::other("The root Cargo.toml has no [workspace] table"))?;
+
+    if workspace.get("dependencies").is_none() {
+        workspace.insert("dependencies", Item::Table(Table::new()));
+    }
+
+    let dependencies = workspace
+        .get_mut("dependencies")
+        .and_then(Item::as_table_like_mut)
+        .ok_or_else(|| io::Error::other("[workspace.dependencies] is not a table"))?;
+
+    if dependencies.contains_key(crate_name) {
+        return Ok(false);
+    }
+
+    let entry = if default_features && registry.is_none() {
+        Value::from(version)
+    } else {
+        let mut entry = InlineTable::new();
+        entry.insert("version", Value::from(version));
+        if let Some(registry) = registry {
+            entry.insert("registry", Value::from(registry));
+        }
+        if !default_features {
+            entry.insert("default-features", Value::from(false));
+        }
+        Value::InlineTable(entry)
+    };
+
+    dependencies.insert(crate_name, Item::Value(entry));
+
+    fs::write(root_manifest, manifest.to_string())?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cache::test_dir;
+
+    use super::*;
+
+    #[test]
+    fn members_are_read_from_cargo_metadata() {
+        let metadata: Metadata = serde_json::from_str(
+            r#"{
+                "packages": [
+                    { "name": "api", "id": "path+file:///ws/api#0.1.0", "manifest_path": "/ws/api/Cargo.toml" },
+                    { "name": "cli", "id": "path+file:///ws/cli#0.1.0", "manifest_path": "/ws/cli/Cargo.toml" }
+                ],
+                "workspace_members": ["path+file:///ws/api#0.1.0", "path+file:///ws/cli#0.1.0"],
+                "workspace_root": "/ws"
+            }"#,
+        )
+        .unwrap();
+
+        let workspace =
+            Workspace::from_metadata(metadata, Some(PathBuf::from("/ws/cli/Cargo.toml")));
+
+        assert_eq!(workspace.root_manifest, PathBuf::from("/ws/Cargo.toml"));
+        assert_eq!(workspace.members.len(), 2);
+        assert_eq!(workspace.current_package.as_deref(), Some("cli"));
+        assert!(workspace.has_many_packages());
+    }
+
+    #[test]
+    fn workspace_dependencies_keep_the_rest_of_the_manifest() {
+        let dir = test_dir("workspace");
+        let root_manifest = dir.join("Cargo.toml");
+
+        fs::write(
+            &root_manifest,
+            "[workspace]\n# the members\nmembers = [\"api\"]\n\n[workspace.dependencies]\nanyhow = \"1\"\n",
+        )
+        .unwrap();
+
+        assert!(add_workspace_dependency(&root_manifest, "tokio", "1.38.0", false, None).unwrap());
+        assert!(!add_workspace_dependency(&root_manifest, "anyhow", "1.0.80", true, None).unwrap());
+
+        assert_eq!(
+            fs::read_to_string(&root_manifest).unwrap(),
+            "[workspace]\n# the members\nmembers = [\"api\"]\n\n[workspace.dependencies]\nanyhow = \"1\"\ntokio = { version = \"1.38.0\", default-features = false }\n"
+        );
+
+        fs::remove_dir_all(dir).ok();
+    }
+}